@@ -0,0 +1,48 @@
+use advent_of_rust::days::{day01, Part};
+use advent_of_rust::utils::columns::Solver;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+const INPUT: &str = include_str!("../src/days/day01/input.txt");
+
+/// The real input repeated `factor` times, shifting every copy so the lists
+/// don't just contain each id `factor` times over.
+fn upscale(factor: u32) -> String {
+    (0..factor)
+        .flat_map(|copy| {
+            INPUT.lines().map(move |line| {
+                let (left, right) = line.split_once("   ").unwrap();
+                let shift =
+                    |id: &str| (id.parse::<u32>().unwrap() + copy * 7_919) % 90_000 + 10_000;
+                format!("{}   {}\n", shift(left), shift(right))
+            })
+        })
+        .collect()
+}
+
+fn location_lists(c: &mut Criterion) {
+    let upscaled = upscale(100);
+    let inputs = [("real", INPUT), ("upscaled_x100", upscaled.as_str())];
+
+    for (part_name, part) in [("part_one", Part::One), ("part_two", Part::Two)] {
+        let mut group = c.benchmark_group(format!("day01_{}", part_name));
+
+        for (input_name, input) in inputs {
+            for (solver_name, solver) in [
+                ("comparison", Solver::Comparison),
+                ("counting", Solver::Counting),
+            ] {
+                group.bench_with_input(
+                    BenchmarkId::new(solver_name, input_name),
+                    input,
+                    |b, input| b.iter(|| day01::solve(black_box(input), part, solver)),
+                );
+            }
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, location_lists);
+criterion_main!(benches);
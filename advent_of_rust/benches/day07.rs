@@ -0,0 +1,30 @@
+use advent_of_rust::days::day07::{solve, Evaluation, Operator};
+use advent_of_rust::utils::operator_search::Strategy;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+const INPUT: &str = include_str!("../src/days/day07/input.txt");
+
+fn calibration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day07_part_two");
+    let operators = [Operator::Add, Operator::Multiply, Operator::Concat];
+
+    for (name, strategy, evaluation) in [
+        ("forward", Strategy::Forward, Evaluation::Sequential),
+        ("backward", Strategy::Backward, Evaluation::Sequential),
+        (
+            "backward_parallel",
+            Strategy::Backward,
+            Evaluation::Parallel,
+        ),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| solve(black_box(INPUT), &operators, strategy, evaluation))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, calibration);
+criterion_main!(benches);
@@ -1,44 +1,53 @@
-use super::Day;
-use std::collections::HashMap;
+use super::{Day, Part};
+use crate::profile;
+use crate::utils::columns::{self, Columns, Integer, Solver};
 
 pub struct DayStruct;
 impl Day for DayStruct {
     type Output = i32;
 
     fn part_one(&self, input: &str) -> Self::Output {
-        let (mut left, mut right) = parse(input);
-        left.sort_unstable();
-        right.sort_unstable();
-
-        left.iter().zip(&right).map(|(l, r)| (l - r).abs()).sum()
+        solve(input, Part::One, Solver::Comparison)
     }
 
     fn part_two(&self, input: &str) -> Self::Output {
-        let (left, right) = parse(input);
-        let right_counts: HashMap<i32, i32> =
-            right
-                .iter()
-                .fold(HashMap::with_capacity(right.len()), |mut map, num| {
-                    *map.entry(*num).or_insert(0) += 1;
-                    map
-                });
-
-        left.iter()
-            .map(|num| right_counts.get(num).unwrap_or(&0) * num)
-            .sum()
+        solve(input, Part::Two, Solver::Comparison)
     }
 }
 
-fn parse(input: &str) -> (Vec<i32>, Vec<i32>) {
-    input
-        .lines()
-        .map(|line| {
-            let mut split = line.split_whitespace();
-            let left = split.next().unwrap().parse::<i32>().unwrap();
-            let right = split.next().unwrap().parse::<i32>().unwrap();
-            (left, right)
-        })
-        .unzip()
+pub fn solve(input: &str, part: Part, solver: Solver) -> i32 {
+    let answer = match (solver, part) {
+        (Solver::Comparison, Part::One) => {
+            let (left, right) = parse::<i32>(input);
+            columns::sorted_distance(&left, &right)
+        }
+        (Solver::Comparison, Part::Two) => {
+            let (left, right) = parse::<i32>(input);
+            columns::similarity(&left, &right)
+        }
+        (Solver::Counting, Part::One) => {
+            let (left, right) = parse::<u32>(input);
+            columns::sorted_distance_radix(&left, &right)
+        }
+        (Solver::Counting, Part::Two) => {
+            let (left, right) = parse::<u32>(input);
+            columns::similarity_counting(&left, &right)
+        }
+    };
+
+    answer.try_into().expect("answer exceeds i32")
+}
+
+fn parse<T: Integer>(input: &str) -> (Vec<T>, Vec<T>) {
+    profile::parsing(|| {
+        let columns = Columns::<T>::parse(input).expect("malformed location id lists");
+        let [left, right]: [Vec<T>; 2] = columns
+            .into_columns()
+            .try_into()
+            .expect("expected exactly two location id lists");
+
+        (left, right)
+    })
 }
 
 #[cfg(test)]
@@ -60,4 +69,10 @@ mod day1_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_counting_solver_example() {
+        assert_eq!(solve(EXAMPLE, Part::One, Solver::Counting), 11);
+        assert_eq!(solve(EXAMPLE, Part::Two, Solver::Counting), 31);
+    }
 }
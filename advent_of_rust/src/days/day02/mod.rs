@@ -1,88 +1,331 @@
-use super::Day;
+use super::{Day, Explain, Part};
+use crate::profile;
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trend {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionRule {
+    /// strictly ascending or strictly descending, decided by the first pair
+    Either,
+    Only(Trend),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "reason")]
+pub enum ViolationKind {
+    Equal,
+    StepTooSmall { step: u32 },
+    StepTooLarge { step: u32 },
+    DirectionChange,
+}
+
+/// The first adjacent pair of levels breaking the rule, by index into the
+/// original report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub left: usize,
+    pub right: usize,
+    #[serde(flatten)]
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    /// Trend of the report as given, or `None` if it has fewer than two levels
+    pub trend: Option<Trend>,
+    /// First violation in the report as given
+    pub violation: Option<Violation>,
+    /// Indices whose removal makes the report safe (empty if it already is),
+    /// or `None` if no removal within the rule's budget does
+    pub removed: Option<Vec<usize>>,
+}
+
+impl Verdict {
+    pub fn is_safe(&self) -> bool {
+        self.removed.is_some()
+    }
+}
+
+pub struct SafetyRule {
+    pub steps: RangeInclusive<u32>,
+    pub direction: DirectionRule,
+    pub max_removals: usize,
+}
+
+impl SafetyRule {
+    pub fn check(&self, report: &[i32]) -> Verdict {
+        let trend = match (self.direction, report) {
+            (DirectionRule::Only(trend), [_, _, ..]) => Some(trend),
+            (DirectionRule::Either, [a, b, ..]) if a < b => Some(Trend::Ascending),
+            (DirectionRule::Either, [_, _, ..]) => Some(Trend::Descending),
+            _ => None,
+        };
+        let violation = trend.and_then(|trend| self.first_violation(report, &[], trend));
+
+        let removed = match (trend, violation) {
+            (_, None) => Some(vec![]),
+            (_, Some(_)) if self.max_removals == 0 => None,
+            (Some(trend), Some(_)) => {
+                let candidates = match self.direction {
+                    DirectionRule::Only(_) => vec![trend],
+                    DirectionRule::Either => vec![trend, trend.reverse()],
+                };
+
+                candidates
+                    .into_iter()
+                    .find_map(|trend| self.dampen(report, &mut vec![], self.max_removals, trend))
+            }
+            (None, Some(_)) => unreachable!(),
+        };
+
+        Verdict {
+            trend,
+            violation,
+            removed,
+        }
+    }
+
+    /// Any set of removals that fixes the report for a fixed trend has to
+    /// break up the first violating pair, so only its two levels are worth
+    /// trying. That bounds the search to 2^k rescans, i.e. linear for k = 1.
+    fn dampen(
+        &self,
+        report: &[i32],
+        removed: &mut Vec<usize>,
+        budget: usize,
+        trend: Trend,
+    ) -> Option<Vec<usize>> {
+        let Some(violation) = self.first_violation(report, removed, trend) else {
+            let mut removed = removed.clone();
+            removed.sort_unstable();
+            return Some(removed);
+        };
+
+        if budget == 0 {
+            return None;
+        }
+
+        for index in [violation.left, violation.right] {
+            removed.push(index);
+            let fixed = self.dampen(report, removed, budget - 1, trend);
+            removed.pop();
+
+            if fixed.is_some() {
+                return fixed;
+            }
+        }
+
+        None
+    }
+
+    fn first_violation(
+        &self,
+        report: &[i32],
+        removed: &[usize],
+        trend: Trend,
+    ) -> Option<Violation> {
+        let mut levels = report
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !removed.contains(i));
+        let mut previous = levels.next()?;
+
+        for current in levels {
+            if let Some(kind) = self.check_pair(*previous.1, *current.1, trend) {
+                return Some(Violation {
+                    left: previous.0,
+                    right: current.0,
+                    kind,
+                });
+            }
+
+            previous = current;
+        }
+
+        None
+    }
+
+    fn check_pair(&self, a: i32, b: i32, trend: Trend) -> Option<ViolationKind> {
+        let step = a.abs_diff(b);
+
+        if a == b {
+            Some(ViolationKind::Equal)
+        } else if (a < b) != (trend == Trend::Ascending) {
+            Some(ViolationKind::DirectionChange)
+        } else if step < *self.steps.start() {
+            Some(ViolationKind::StepTooSmall { step })
+        } else if step > *self.steps.end() {
+            Some(ViolationKind::StepTooLarge { step })
+        } else {
+            None
+        }
+    }
+}
+
+impl Trend {
+    fn reverse(self) -> Trend {
+        match self {
+            Trend::Ascending => Trend::Descending,
+            Trend::Descending => Trend::Ascending,
+        }
+    }
+}
+
+impl fmt::Display for Trend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trend::Ascending => write!(f, "ascending"),
+            Trend::Descending => write!(f, "descending"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReportExplanation {
+    pub line: usize,
+    pub levels: Vec<i32>,
+    pub safe: bool,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
 
 pub struct DayStruct;
 impl Day for DayStruct {
     type Output = usize;
 
     fn part_one(&self, input: &str) -> Self::Output {
-        input
-            .lines()
-            .map(|report| {
-                report
-                    .split_whitespace()
-                    .map(|num| num.parse::<i32>().unwrap())
-                    .collect::<Vec<_>>()
-            })
-            .filter(|report| {
-                let levels_ascending = report[0] < report[1];
-                report
-                    .windows(2)
-                    .all(|window| is_level_ok(window[0], window[1], levels_ascending))
-            })
-            .count()
+        count_safe(input, &rule_for(Part::One))
     }
 
     fn part_two(&self, input: &str) -> Self::Output {
-        input
-            .lines()
-            .map(|report| {
-                report
-                    .split_whitespace()
-                    .map(|num| num.parse::<i32>().unwrap())
-                    .collect::<Vec<_>>()
-            })
-            .filter(|report| {
-                if report
-                    .windows(2)
-                    .all(|window| is_level_ok(window[0], window[1], report[0] < report[1]))
-                {
-                    return true;
-                }
+        count_safe(input, &rule_for(Part::Two))
+    }
+}
 
-                for i in 0..report.len() {
-                    let mut levels = report[..i].iter().chain(report[(i + 1)..].iter());
-                    let mut a = levels.next().unwrap();
-                    let mut levels_ascending = None;
-
-                    let report_ok = levels.all(|b| {
-                        if a == b || a.abs_diff(*b) > 3 {
-                            return false;
-                        }
-
-                        match levels_ascending {
-                            None => levels_ascending = Some(a > b),
-                            Some(true) if a < b => return false,
-                            Some(false) if a > b => return false,
-                            _ => {}
-                        }
-
-                        a = b;
-                        true
-                    });
-
-                    if report_ok {
-                        return true;
-                    }
-                }
+fn rule_for(part: Part) -> SafetyRule {
+    SafetyRule {
+        steps: 1..=3,
+        direction: DirectionRule::Either,
+        max_removals: match part {
+            Part::One => 0,
+            Part::Two => 1,
+        },
+    }
+}
+
+fn count_safe(input: &str, rule: &SafetyRule) -> usize {
+    parse(input)
+        .filter(|report| rule.check(report).is_safe())
+        .count()
+}
+
+fn parse(input: &str) -> impl Iterator<Item = Vec<i32>> + '_ {
+    input.lines().map(|report| {
+        profile::parsing(|| {
+            report
+                .split_whitespace()
+                .map(|num| num.parse::<i32>().unwrap())
+                .collect()
+        })
+    })
+}
 
-                false
+/// One record per report: whether it is safe, its trend, the first violating
+/// pair of levels and, for part two, which level the dampener removes.
+impl Explain for DayStruct {
+    type Record = ReportExplanation;
+
+    fn explain(&self, input: &str, part: Part) -> Vec<ReportExplanation> {
+        let rule = rule_for(part);
+
+        parse(input)
+            .enumerate()
+            .map(|(i, levels)| {
+                let verdict = rule.check(&levels);
+                ReportExplanation {
+                    line: i + 1,
+                    levels,
+                    safe: verdict.is_safe(),
+                    verdict,
+                }
             })
+            .collect()
+    }
+
+    fn describe(&self, record: &ReportExplanation, part: Part) -> String {
+        describe(record, &rule_for(part))
+    }
+
+    fn answer(&self, records: &[ReportExplanation], _input: &str, _part: Part) -> String {
+        records
+            .iter()
+            .filter(|record| record.safe)
             .count()
+            .to_string()
     }
 }
 
-fn is_level_ok(level_one: i32, level_two: i32, levels_ascending: bool) -> bool {
-    // levels can not be equivalent (min difference of one)
-    level_one != level_two
-    // all levels must be ascending or descending
-    && (level_one < level_two) == levels_ascending
-    // maximum difference of 3
-    && level_one.abs_diff(level_two) <= 3
+fn describe(explanation: &ReportExplanation, rule: &SafetyRule) -> String {
+    let ReportExplanation {
+        line,
+        levels,
+        verdict,
+        ..
+    } = explanation;
+    let joined: Vec<String> = levels.iter().map(i32::to_string).collect();
+    let mut text = format!("line {}: {}", line, joined.join(" "));
+
+    if let Some(trend) = verdict.trend {
+        write!(text, " ({})", trend).unwrap();
+    }
+
+    match &verdict.removed {
+        Some(removed) if removed.is_empty() => text.push_str(" -> safe"),
+        Some(removed) => {
+            let removed: Vec<String> = removed
+                .iter()
+                .map(|&i| format!("level {} ({})", i, levels[i]))
+                .collect();
+            write!(text, " -> safe after removing {}", removed.join(", ")).unwrap();
+        }
+        None => text.push_str(" -> unsafe"),
+    }
+
+    if let Some(Violation { left, right, kind }) = verdict.violation {
+        let (a, b) = (levels[left], levels[right]);
+        let reason = match kind {
+            ViolationKind::Equal => "levels are equal".to_string(),
+            ViolationKind::StepTooSmall { step } => {
+                format!("step of {} is below {}", step, rule.steps.start())
+            }
+            ViolationKind::StepTooLarge { step } => {
+                format!("step of {} is above {}", step, rule.steps.end())
+            }
+            ViolationKind::DirectionChange => "direction changes".to_string(),
+        };
+        write!(
+            text,
+            "; first violation at levels {}..={} ({} -> {}): {}",
+            left, right, a, b, reason
+        )
+        .unwrap();
+    }
+
+    text
 }
 
 #[cfg(test)]
 mod day2_tests {
     use super::*;
+    use crate::days::{explain, ExplainFormat};
 
     const EXAMPLE: &str = include_str!("./example.txt");
 
@@ -99,4 +342,81 @@ mod day2_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_first_violation_and_removal() {
+        let rule = SafetyRule {
+            steps: 1..=3,
+            direction: DirectionRule::Either,
+            max_removals: 1,
+        };
+
+        let verdict = rule.check(&[1, 3, 2, 4, 5]);
+        assert_eq!(
+            verdict.violation,
+            Some(Violation {
+                left: 1,
+                right: 2,
+                kind: ViolationKind::DirectionChange
+            })
+        );
+        assert_eq!(verdict.removed, Some(vec![1]));
+
+        // the first level sets the wrong direction, only removing it helps
+        assert_eq!(rule.check(&[5, 6, 4, 3, 2]).removed, Some(vec![0]));
+        assert_eq!(rule.check(&[1, 2, 7, 8, 9]).removed, None);
+    }
+
+    #[test]
+    fn test_explain_example() {
+        let text = explain(&DayStruct, EXAMPLE, Part::Two, ExplainFormat::Text);
+        let json = explain(&DayStruct, EXAMPLE, Part::Two, ExplainFormat::Json);
+
+        assert_eq!(
+            text.lines().nth(3).unwrap(),
+            "line 4: 1 3 2 4 5 (ascending) -> safe after removing level 1 (3); \
+             first violation at levels 1..=2 (3 -> 2): direction changes"
+        );
+        assert_eq!(text.lines().last().unwrap(), "answer: 4");
+        assert_eq!(
+            json.lines().nth(1).unwrap(),
+            r#"{"line":2,"levels":[1,2,7,8,9],"safe":false,"trend":"ascending","violation":{"left":1,"right":2,"reason":"step_too_large","step":5},"removed":null}"#
+        );
+    }
+
+    #[test]
+    fn test_dampener_matches_brute_force() {
+        let rule = SafetyRule {
+            steps: 1..=3,
+            direction: DirectionRule::Either,
+            max_removals: 2,
+        };
+        let strict = SafetyRule {
+            steps: 1..=3,
+            direction: DirectionRule::Either,
+            max_removals: 0,
+        };
+        let reports = [
+            vec![1, 5, 2, 3, 9, 4],
+            vec![9, 1, 8, 7, 7, 6],
+            vec![1, 1, 1, 2],
+            vec![3, 2, 1, 2, 3, 4],
+        ];
+
+        for report in reports {
+            let brute_force = (0..report.len()).any(|i| {
+                (i..report.len()).any(|j| {
+                    let levels: Vec<i32> = report
+                        .iter()
+                        .enumerate()
+                        .filter(|&(k, _)| k != i && k != j)
+                        .map(|(_, &level)| level)
+                        .collect();
+                    strict.check(&levels).is_safe()
+                })
+            });
+
+            assert_eq!(rule.check(&report).is_safe(), brute_force, "{:?}", report);
+        }
+    }
 }
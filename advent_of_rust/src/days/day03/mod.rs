@@ -1,163 +1,305 @@
-use super::Day;
-
-#[derive(PartialEq, Clone, Copy)]
-enum Token {
-    M,
-    U,
-    L,
-    Num,
-    Comma,
-    LParen,
-    RParen,
-}
-
-impl Token {
-    fn valid_next_tokens(&self) -> &[Token] {
-        match &self {
-            Token::M => &[Token::U],
-            Token::U => &[Token::L],
-            Token::L => &[Token::LParen],
-            Token::LParen => &[Token::Num],
-            Token::Num => &[Token::Num, Token::RParen, Token::Comma],
-            Token::Comma => &[Token::Num],
-            Token::RParen => &[],
-        }
-    }
+use super::{Day, Explain, Part};
+use crate::profile;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Mul(i32, i32),
+    Do,
+    Dont,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spanned {
+    instruction: Instruction,
+    span: Range<usize>,
+}
+
+/// A `mul` that the scanner started reading but had to give up on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NearMiss {
+    span: Range<usize>,
+    rejection: Rejection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    MissingLParen(Option<u8>),
+    MissingOperand(Option<u8>),
+    TooManyDigits(usize),
+    MissingComma(Option<u8>),
+    MissingRParen(Option<u8>),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let found = |b: &Option<u8>| match b {
+            Some(b) if !b.is_ascii() => format!("found non-ASCII byte {:#04x}", b),
+            Some(b) => format!("found '{}'", escape_control(&(*b as char).to_string())),
+            None => "found end of input".to_string(),
+        };
 
-    fn from_char(c: char) -> Option<Token> {
-        match c {
-            'm' => Some(Token::M),
-            'u' => Some(Token::U),
-            'l' => Some(Token::L),
-            '(' => Some(Token::LParen),
-            ')' => Some(Token::RParen),
-            ',' => Some(Token::Comma),
-            _ if c.is_numeric() => Some(Token::Num),
-            _ => None,
+        match self {
+            Rejection::MissingLParen(b) => write!(f, "expected '(' after `mul`, {}", found(b)),
+            Rejection::MissingOperand(b) => write!(f, "expected a digit, {}", found(b)),
+            Rejection::TooManyDigits(n) => write!(f, "operand has {} digits, at most 3 allowed", n),
+            Rejection::MissingComma(b) => write!(f, "expected ',' between operands, {}", found(b)),
+            Rejection::MissingRParen(b) => write!(f, "expected ')' after operands, {}", found(b)),
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EnableSemantics {
+    /// `do()`/`don't()` are parsed but have no effect
+    Ignore,
+    /// `don't()` disables every `mul` until the next `do()`
+    Respect,
+}
+
 pub struct DayStruct;
 impl Day for DayStruct {
     type Output = i32;
 
     fn part_one(&self, input: &str) -> Self::Output {
-        let mut output = 0;
-        let mut prev_token: Option<Token> = None;
-        let mut operand_seq: Vec<char> = vec![];
-
-        for ch in input.trim().chars() {
-            match Token::from_char(ch) {
-                Some(token) => {
-                    if token == Token::M {
-                        prev_token = Some(token);
-                        operand_seq.clear();
-                    } else if let Some(prev) = prev_token {
-                        if prev.valid_next_tokens().contains(&token) {
-                            match token {
-                                Token::RParen => {
-                                    output += multiply_chars(&operand_seq);
-                                    prev_token = None;
-                                    operand_seq.clear();
-                                }
-                                Token::Num | Token::Comma => {
-                                    operand_seq.push(ch);
-                                    prev_token = Some(token);
-                                }
-                                _ => prev_token = Some(token),
-                            }
-                        } else {
-                            prev_token = None;
-                            operand_seq.clear();
-                        }
-                    }
-                }
-                None => {
-                    prev_token = None;
-                    operand_seq.clear();
-                }
-            }
-        }
-
-        output
+        execute(&scan(input), EnableSemantics::Ignore)
     }
 
     fn part_two(&self, input: &str) -> Self::Output {
-        let mut output = 0;
-        let mut prev_token: Option<Token> = None;
-        let mut operand_seq: Vec<char> = vec![];
-
-        for ch in input
-            .trim()
-            .split("do()")
-            .flat_map(|s| s.split("don't()").next().unwrap_or("").chars())
-        {
-            match Token::from_char(ch) {
-                Some(token) => {
-                    if token == Token::M {
-                        prev_token = Some(token);
-                        operand_seq.clear();
-                    } else if let Some(prev) = prev_token {
-                        if prev.valid_next_tokens().contains(&token) {
-                            match token {
-                                Token::RParen => {
-                                    output += multiply_chars(&operand_seq);
-                                    prev_token = None;
-                                    operand_seq.clear();
-                                }
-                                Token::Num | Token::Comma => {
-                                    operand_seq.push(ch);
-                                    prev_token = Some(token);
-                                }
-                                _ => prev_token = Some(token),
-                            }
-                        } else {
-                            prev_token = None;
-                            operand_seq.clear();
-                        }
-                    }
+        execute(&scan(input), EnableSemantics::Respect)
+    }
+}
+
+fn execute(instructions: &[Spanned], semantics: EnableSemantics) -> i32 {
+    instructions
+        .iter()
+        .fold((true, 0), |(enabled, sum), spanned| {
+            match (spanned.instruction, semantics) {
+                (Instruction::Mul(a, b), _) if enabled => (enabled, sum + a * b),
+                (Instruction::Do, EnableSemantics::Respect) => (true, sum),
+                (Instruction::Dont, EnableSemantics::Respect) => (false, sum),
+                _ => (enabled, sum),
+            }
+        })
+        .1
+}
+
+fn scan(input: &str) -> Vec<Spanned> {
+    scan_with_near_misses(input).0
+}
+
+fn scan_with_near_misses(input: &str) -> (Vec<Spanned>, Vec<NearMiss>) {
+    profile::parsing(|| {
+        let bytes = input.as_bytes();
+        let mut instructions = vec![];
+        let mut near_misses = vec![];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match scan_instruction(bytes, i) {
+                Ok(Some((instruction, end))) => {
+                    instructions.push(Spanned {
+                        instruction,
+                        span: i..end,
+                    });
+                    i = end;
                 }
-                None => {
-                    prev_token = None;
-                    operand_seq.clear();
+                Ok(None) => i += 1,
+                Err((rejection, at)) => {
+                    near_misses.push(NearMiss {
+                        span: i..input.ceil_char_boundary(at + 1),
+                        rejection,
+                    });
+                    i += 1;
                 }
             }
         }
 
-        output
+        (instructions, near_misses)
+    })
+}
+
+/// Tries to read a single instruction starting exactly at `start`, returning
+/// it along with the index one past its closing parenthesis. Anything starting
+/// with `mul` that fails to parse is reported along with the offending index.
+fn scan_instruction(
+    bytes: &[u8],
+    start: usize,
+) -> Result<Option<(Instruction, usize)>, (Rejection, usize)> {
+    let rest = &bytes[start..];
+
+    if rest.starts_with(b"do()") {
+        Ok(Some((Instruction::Do, start + 4)))
+    } else if rest.starts_with(b"don't()") {
+        Ok(Some((Instruction::Dont, start + 7)))
+    } else if rest.starts_with(b"mul") {
+        let i = expect_byte(bytes, start + 3, b'(', Rejection::MissingLParen)?;
+        let (a, i) = scan_operand(bytes, i)?;
+        let i = expect_byte(bytes, i, b',', Rejection::MissingComma)?;
+        let (b, i) = scan_operand(bytes, i)?;
+        let i = expect_byte(bytes, i, b')', Rejection::MissingRParen)?;
+
+        Ok(Some((Instruction::Mul(a, b), i)))
+    } else {
+        Ok(None)
     }
 }
 
-fn multiply_chars(chars: &[char]) -> i32 {
-    let mut left: Vec<char> = Vec::with_capacity(3);
-    let mut right: Vec<char> = Vec::with_capacity(3);
-    let mut comma_found = false;
-
-    for &ch in chars {
-        match ch {
-            ',' if !comma_found => comma_found = true,
-            ',' => return 0,
-            _ if ch.is_numeric() => {
-                if comma_found {
-                    right.push(ch);
-                } else {
-                    left.push(ch);
+/// Reads a 1-3 digit operand, returning its value and the index after it.
+fn scan_operand(bytes: &[u8], start: usize) -> Result<(i32, usize), (Rejection, usize)> {
+    let digits = bytes[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+
+    match digits {
+        0 => return Err((Rejection::MissingOperand(bytes.get(start).copied()), start)),
+        4.. => return Err((Rejection::TooManyDigits(digits), start + digits - 1)),
+        _ => {}
+    }
+
+    let value = bytes[start..start + digits]
+        .iter()
+        .fold(0, |acc, b| acc * 10 + (b - b'0') as i32);
+
+    Ok((value, start + digits))
+}
+
+fn expect_byte(
+    bytes: &[u8],
+    i: usize,
+    expected: u8,
+    rejection: fn(Option<u8>) -> Rejection,
+) -> Result<usize, (Rejection, usize)> {
+    match bytes.get(i) {
+        Some(&b) if b == expected => Ok(i + 1),
+        found => Err((rejection(found.copied()), i)),
+    }
+}
+
+/// An instruction the scanner accepted or a `mul` it rejected, with its byte
+/// offset and source text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Trace {
+    Accepted {
+        offset: usize,
+        text: String,
+        /// The product, if this is a `mul` that counts towards the total
+        value: Option<i32>,
+        /// Offset of the `don't()` that switched this `mul` off
+        disabled_by: Option<usize>,
+        /// A `do()` or `don't()` in part one
+        ignored: bool,
+    },
+    NearMiss {
+        offset: usize,
+        text: String,
+        reason: String,
+    },
+}
+
+/// Every instruction the scanner accepted (and, for part two, which `mul`s a
+/// `don't()` switched off), followed by every rejected `mul`.
+impl Explain for DayStruct {
+    type Record = Trace;
+
+    fn explain(&self, input: &str, part: Part) -> Vec<Trace> {
+        let semantics = match part {
+            Part::One => EnableSemantics::Ignore,
+            Part::Two => EnableSemantics::Respect,
+        };
+        let (instructions, near_misses) = scan_with_near_misses(input);
+        let snippet = |span: &Range<usize>| escape_control(&input[span.clone()]);
+
+        let mut trace = vec![];
+        let mut disabled_by: Option<usize> = None;
+        for Spanned { instruction, span } in &instructions {
+            let mut ignored = false;
+            match (*instruction, semantics) {
+                (Instruction::Mul(..), _) => {}
+                (Instruction::Do, EnableSemantics::Respect) => disabled_by = None,
+                (Instruction::Dont, EnableSemantics::Respect) => {
+                    disabled_by = disabled_by.or(Some(span.start))
                 }
+                _ => ignored = true,
             }
-            _ => {}
+
+            let is_mul = matches!(instruction, Instruction::Mul(..));
+            trace.push(Trace::Accepted {
+                offset: span.start,
+                text: snippet(span),
+                value: match *instruction {
+                    Instruction::Mul(a, b) if disabled_by.is_none() => Some(a * b),
+                    _ => None,
+                },
+                disabled_by: disabled_by.filter(|_| is_mul),
+                ignored,
+            });
         }
+
+        trace.extend(
+            near_misses
+                .iter()
+                .map(|NearMiss { span, rejection }| Trace::NearMiss {
+                    offset: span.start,
+                    text: snippet(span),
+                    reason: rejection.to_string(),
+                }),
+        );
+
+        trace
     }
 
-    let l: i32 = left.into_iter().collect::<String>().parse().unwrap_or(0);
-    let r: i32 = right.into_iter().collect::<String>().parse().unwrap_or(0);
+    fn describe(&self, record: &Trace, _part: Part) -> String {
+        let (offset, text, status) = match record {
+            Trace::Accepted {
+                offset,
+                text,
+                value,
+                disabled_by,
+                ignored,
+            } => {
+                let status = match (value, disabled_by) {
+                    (Some(value), _) => format!("= {}", value),
+                    (None, Some(offset)) => format!("disabled by don't() at {}", offset),
+                    _ if *ignored => "ignored".to_string(),
+                    _ => String::new(),
+                };
+                (offset, text, status)
+            }
+            Trace::NearMiss {
+                offset,
+                text,
+                reason,
+            } => (offset, text, format!("rejected: {}", reason)),
+        };
+
+        format!("{:>6}  {:<14}{}", offset, text, status)
+            .trim_end()
+            .to_string()
+    }
+}
 
-    l * r
+// keeps snippets on one line without turning `don't()` into `don\'t()`
+fn escape_control(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_debug().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod day3_tests {
     use super::*;
+    use crate::days::{explain, ExplainFormat};
 
     const EXAMPLE: &str = include_str!("./example.txt");
 
@@ -174,4 +316,72 @@ mod day3_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_scan_instruction_stream() {
+        let instructions: Vec<_> = scan("mul(1,2)mul(1234,5)don't()mul(mul(3,4)do()")
+            .into_iter()
+            .map(|spanned| (spanned.instruction, spanned.span))
+            .collect();
+
+        assert_eq!(
+            instructions,
+            vec![
+                (Instruction::Mul(1, 2), 0..8),
+                (Instruction::Dont, 19..26),
+                (Instruction::Mul(3, 4), 30..38),
+                (Instruction::Do, 38..42),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let (_, near_misses) = scan_with_near_misses("mul(4*mul ( 2 , 4 )mul(1234,5)mul(2,4]");
+        let rejections: Vec<_> = near_misses
+            .into_iter()
+            .map(|near_miss| (near_miss.span, near_miss.rejection))
+            .collect();
+
+        assert_eq!(
+            rejections,
+            vec![
+                (0..6, Rejection::MissingComma(Some(b'*'))),
+                (6..10, Rejection::MissingLParen(Some(b' '))),
+                (19..27, Rejection::TooManyDigits(4)),
+                (30..38, Rejection::MissingRParen(Some(b']'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_part_two_example() {
+        let explanation = explain(&DayStruct, EXAMPLE, Part::Two, ExplainFormat::Text);
+
+        assert!(explanation.contains("mul(5,5)      disabled by don't() at 20"));
+        assert!(explanation.contains("mul(8,5)      = 40"));
+        assert!(explanation.contains("answer: 48"));
+        assert!(explanation.contains("mul[          rejected: expected '(' after `mul`, found '['"));
+
+        let json = explain(&DayStruct, EXAMPLE, Part::Two, ExplainFormat::Json);
+        assert_eq!(
+            json.lines().nth(2).unwrap(),
+            r#"{"kind":"accepted","offset":28,"text":"mul(5,5)","value":null,"disabled_by":20,"ignored":false}"#
+        );
+    }
+
+    #[test]
+    fn test_explain_non_ascii() {
+        let input = "mul(é)mul(2,3)ü mul(4,é";
+        let explanation = explain(&DayStruct, input, Part::One, ExplainFormat::Text);
+        let lines: Vec<&str> = explanation.lines().collect();
+
+        assert_eq!(DayStruct.part_one(input), 6);
+        assert_eq!(
+            lines[1],
+            "     0  mul(é         rejected: expected a digit, found non-ASCII byte 0xc3"
+        );
+        assert!(lines[2].contains("mul(4,é"));
+        assert_eq!(lines[3], "answer: 6");
+    }
 }
@@ -1,5 +1,24 @@
 use super::Day;
+use crate::profile;
 use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Page -> every page that has to come after it.
+type Rules = HashMap<i32, HashSet<i32>>;
+
+/// Diagnostics for a single update, see [`diagnose`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateReport {
+    pub pages: Vec<i32>,
+    /// Rules `before|after` broken by the update, in rule order
+    pub violations: Vec<(i32, i32)>,
+    /// The update sorted by the rules, or `None` if they contain a cycle
+    /// among its pages
+    pub corrected: Option<Vec<i32>>,
+    /// Whether the rules restricted to the update's pages form a total order,
+    /// i.e. there is exactly one correct ordering
+    pub unique: bool,
+}
 
 pub struct DayStruct;
 impl Day for DayStruct {
@@ -72,28 +91,190 @@ impl Day for DayStruct {
     }
 }
 
-fn parse(input: &str) -> (HashMap<i32, HashSet<i32>>, Vec<Vec<i32>>) {
-    let (page_ordering_rules_raw, update_raw) = input.split_once("\n\n").unwrap();
+fn parse(input: &str) -> (Rules, Vec<Vec<i32>>) {
+    profile::parsing(|| {
+        let (page_ordering_rules_raw, update_raw) = input.split_once("\n\n").unwrap();
 
-    let mut page_ordering_rules: HashMap<i32, HashSet<i32>> = HashMap::new();
-    page_ordering_rules_raw.lines().for_each(|line| {
-        let mut parts = line.split('|');
-        let key = parts.next().unwrap().parse::<i32>().unwrap();
-        let value = parts.next().unwrap().parse::<i32>().unwrap();
+        let mut page_ordering_rules: Rules = HashMap::new();
+        page_ordering_rules_raw.lines().for_each(|line| {
+            let mut parts = line.split('|');
+            let key = parts.next().unwrap().parse::<i32>().unwrap();
+            let value = parts.next().unwrap().parse::<i32>().unwrap();
 
-        page_ordering_rules.entry(key).or_default().insert(value);
-    });
+            page_ordering_rules.entry(key).or_default().insert(value);
+        });
 
-    let updates: Vec<Vec<i32>> = update_raw
-        .lines()
-        .map(|s| {
-            s.split(",")
-                .map(|num| num.parse::<i32>().unwrap())
-                .collect()
+        let updates: Vec<Vec<i32>> = update_raw
+            .lines()
+            .map(|s| {
+                s.split(",")
+                    .map(|num| num.parse::<i32>().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        (page_ordering_rules, updates)
+    })
+}
+
+fn rules_within<'a>(rules: &'a Rules, pages: &'a [i32]) -> impl Iterator<Item = (i32, i32)> + 'a {
+    let mut before: Vec<i32> = pages.to_vec();
+    before.sort_unstable();
+    before.dedup();
+
+    before.into_iter().flat_map(move |page| {
+        let mut after: Vec<i32> = rules
+            .get(&page)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|after| pages.contains(after))
+            .collect();
+        after.sort_unstable();
+        after.into_iter().map(move |after| (page, after))
+    })
+}
+
+/// Checks every update against the rules: which rules it breaks, how it should
+/// be ordered and whether that order is the only one the rules allow.
+pub fn diagnose(input: &str) -> Vec<UpdateReport> {
+    let (rules, updates) = parse(input);
+
+    updates
+        .into_iter()
+        .map(|pages| {
+            let position = |page: i32| pages.iter().position(|&p| p == page).unwrap();
+            let violations = rules_within(&rules, &pages)
+                .filter(|&(before, after)| position(after) < position(before))
+                .collect();
+            let (corrected, unique) = match topological_order(&rules, &pages) {
+                Some((order, unique)) => (Some(order), unique),
+                None => (None, false),
+            };
+
+            UpdateReport {
+                pages,
+                violations,
+                corrected,
+                unique,
+            }
         })
-        .collect();
+        .collect()
+}
+
+/// Kahn's algorithm over the rules restricted to `pages`, breaking ties by the
+/// update's own order. The order is unique iff there never was a tie.
+fn topological_order(rules: &Rules, pages: &[i32]) -> Option<(Vec<i32>, bool)> {
+    let mut in_degree: HashMap<i32, usize> = pages.iter().map(|&page| (page, 0)).collect();
+    for (_, after) in rules_within(rules, pages) {
+        *in_degree.get_mut(&after).unwrap() += 1;
+    }
+
+    let mut order = Vec::with_capacity(pages.len());
+    let mut unique = true;
+    while order.len() < in_degree.len() {
+        let mut ready = pages
+            .iter()
+            .filter(|page| in_degree.get(page) == Some(&0) && !order.contains(*page));
+        let &page = ready.next()?;
+        unique &= ready.next().is_none();
+
+        order.push(page);
+        for after in rules.get(&page).into_iter().flatten() {
+            if let Some(degree) = in_degree.get_mut(after) {
+                *degree -= 1;
+            }
+        }
+    }
+
+    Some((order, unique))
+}
+
+/// Text report of [`diagnose`], one block per update.
+pub fn report(input: &str) -> String {
+    let join = |pages: &[i32]| {
+        pages
+            .iter()
+            .map(i32::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut out = String::new();
+
+    for (i, update) in diagnose(input).iter().enumerate() {
+        let ordering = if update.unique { "unique" } else { "ambiguous" };
+        let status = if update.violations.is_empty() {
+            "ok"
+        } else {
+            "out of order"
+        };
+        writeln!(
+            out,
+            "update {}: {} -> {}",
+            i + 1,
+            join(&update.pages),
+            status
+        )
+        .unwrap();
 
-    (page_ordering_rules, updates)
+        for (before, after) in &update.violations {
+            writeln!(out, "  violates {}|{}", before, after).unwrap();
+        }
+        match &update.corrected {
+            Some(corrected) => {
+                writeln!(out, "  corrected: {} ({})", join(corrected), ordering).unwrap()
+            }
+            None => writeln!(out, "  corrected: none, the rules contain a cycle").unwrap(),
+        }
+    }
+
+    out
+}
+
+/// The rules as a Graphviz digraph, `before -> after`. When `update` (1-based)
+/// is given, only the rules between its pages are included and the ones it
+/// breaks are drawn in red.
+pub fn to_dot(input: &str, update: Option<usize>) -> Result<String, String> {
+    let (rules, updates) = parse(input);
+    let mut out = String::from("digraph page_ordering {\n");
+
+    let edges: Vec<((i32, i32), bool)> = match update {
+        Some(n) => {
+            let pages = updates
+                .get(n.wrapping_sub(1))
+                .ok_or_else(|| format!("there is no update {}, only {}", n, updates.len()))?;
+            let position = |page: i32| pages.iter().position(|&p| p == page).unwrap();
+
+            for (i, page) in pages.iter().enumerate() {
+                writeln!(out, "    {} [label=\"{} (#{})\"];", page, page, i + 1).unwrap();
+            }
+            rules_within(&rules, pages)
+                .map(|(before, after)| ((before, after), position(after) < position(before)))
+                .collect()
+        }
+        None => {
+            let mut edges: Vec<((i32, i32), bool)> = rules
+                .iter()
+                .flat_map(|(&before, after)| {
+                    after.iter().map(move |&after| ((before, after), false))
+                })
+                .collect();
+            edges.sort_unstable();
+            edges
+        }
+    };
+
+    for ((before, after), violated) in edges {
+        let style = if violated {
+            " [color=red, penwidth=2]"
+        } else {
+            ""
+        };
+        writeln!(out, "    {} -> {}{};", before, after, style).unwrap();
+    }
+    out.push_str("}\n");
+
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -115,4 +296,31 @@ mod day5_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_diagnose_example() {
+        let reports = diagnose(EXAMPLE);
+
+        assert!(reports[0].violations.is_empty());
+        assert_eq!(reports[3].violations, vec![(97, 75)]);
+        assert_eq!(reports[3].corrected, Some(vec![97, 75, 47, 61, 53]));
+        assert!(reports.iter().all(|report| report.unique));
+
+        let ambiguous = diagnose("1|2\n1|3\n\n3,2,1");
+        assert_eq!(ambiguous[0].violations, vec![(1, 2), (1, 3)]);
+        assert_eq!(ambiguous[0].corrected, Some(vec![1, 3, 2]));
+        assert!(!ambiguous[0].unique);
+    }
+
+    #[test]
+    fn test_dot_for_update() {
+        let dot = to_dot("1|2\n2|3\n4|1\n\n2,1", Some(1)).unwrap();
+
+        assert_eq!(
+            dot,
+            "digraph page_ordering {\n    2 [label=\"2 (#1)\"];\n    1 [label=\"1 (#2)\"];\n    \
+             1 -> 2 [color=red, penwidth=2];\n}\n"
+        );
+        assert!(to_dot("1|2\n\n2,1", Some(2)).is_err());
+    }
 }
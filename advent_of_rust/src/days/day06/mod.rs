@@ -1,6 +1,14 @@
-use super::Day;
-
-#[derive(PartialEq)]
+use super::{Day, Part, Visualize};
+use crate::profile;
+use crate::utils::animation::{Frame, FrameSink};
+use crate::utils::cycle;
+use crate::utils::export::ColourMap;
+use crate::utils::grid::Grid;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::iter::successors;
+
+#[derive(Clone, Copy, PartialEq)]
 enum Token {
     Wall,
     Floor,
@@ -15,7 +23,17 @@ enum Direction {
     Right,
 }
 
+/// Position and facing of the guard.
+type Guard = ((usize, usize), Direction);
+
 impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
     fn from_char(ch: char) -> Option<Direction> {
         match ch {
             '^' => Some(Direction::Up),
@@ -26,21 +44,25 @@ impl Direction {
         }
     }
 
-    fn to_bit(self) -> u8 {
+    fn to_char(self) -> char {
         match self {
-            Direction::Up => 0b0001,
-            Direction::Right => 0b0010,
-            Direction::Down => 0b0100,
-            Direction::Left => 0b1000,
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
         }
     }
 
-    fn next_tile(&self, i: i32, j: i32) -> (i32, i32) {
+    fn is_vertical(&self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    fn delta(&self) -> (isize, isize) {
         match self {
-            Direction::Up => (i - 1, j),
-            Direction::Down => (i + 1, j),
-            Direction::Left => (i, j - 1),
-            Direction::Right => (i, j + 1),
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
         }
     }
 
@@ -52,6 +74,26 @@ impl Direction {
             Direction::Left => Direction::Up,
         }
     }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    /// Number of steps in this direction from `from` to `to`, if `to` lies on
+    /// that ray.
+    fn distance(&self, from: (usize, usize), to: (usize, usize)) -> Option<usize> {
+        match self {
+            Direction::Up => (to.1 == from.1 && to.0 <= from.0).then(|| from.0 - to.0),
+            Direction::Down => (to.1 == from.1 && to.0 >= from.0).then(|| to.0 - from.0),
+            Direction::Left => (to.0 == from.0 && to.1 <= from.1).then(|| from.1 - to.1),
+            Direction::Right => (to.0 == from.0 && to.1 >= from.1).then(|| to.1 - from.1),
+        }
+    }
 }
 
 impl Token {
@@ -64,152 +106,264 @@ impl Token {
     }
 }
 
+/// For every cell and direction, where the guard stops when walking that way:
+/// the cell just before the next wall, or `None` if that leaves the map.
+struct JumpTable {
+    stops: [Grid<Option<(usize, usize)>>; 4],
+}
+
+impl JumpTable {
+    fn new(grid: &Grid<Token>) -> JumpTable {
+        JumpTable {
+            stops: Direction::ALL.map(|dir| {
+                let mut stops = grid.map(|_| None);
+                let mut positions: Vec<_> = grid.positions().collect();
+                // fill in the cell ahead of each position before the position itself
+                if matches!(dir, Direction::Down | Direction::Right) {
+                    positions.reverse();
+                }
+
+                for pos in positions {
+                    stops[pos] = match grid.offset(pos, dir.delta()) {
+                        None => None,
+                        Some(next) if grid[next] == Token::Wall => Some(pos),
+                        Some(next) => stops[next],
+                    };
+                }
+
+                stops
+            }),
+        }
+    }
+
+    /// Where the guard at `pos` facing `dir` stops, with one extra wall at
+    /// `obstacle` laid over the table rather than rebuilding it.
+    fn jump(
+        &self,
+        pos: (usize, usize),
+        dir: Direction,
+        obstacle: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let stop = self.stops[dir.index()][pos];
+
+        match dir.distance(pos, obstacle) {
+            Some(steps)
+                if steps > 0
+                    && stop.is_none_or(|stop| steps <= dir.distance(pos, stop).unwrap()) =>
+            {
+                let (d_row, d_col) = dir.delta();
+                Some((
+                    obstacle.0.wrapping_add_signed(-d_row),
+                    obstacle.1.wrapping_add_signed(-d_col),
+                ))
+            }
+            _ => stop,
+        }
+    }
+
+    /// Whether the guard gets stuck in a loop once `obstacle` is added.
+    fn loops(&self, guard: Guard, obstacle: (usize, usize)) -> bool {
+        cycle::brent(guard, |&(pos, dir)| {
+            self.jump(pos, dir, obstacle)
+                .map(|stop| (stop, dir.turn_right()))
+        })
+        .is_some()
+    }
+}
+
 pub struct DayStruct;
 impl Day for DayStruct {
     type Output = i32;
 
     fn part_one(&self, input: &str) -> Self::Output {
-        let (dir, row_start, col_start) = get_start(input);
-        let mut tokens: Vec<Vec<Token>> = input
-            .lines()
-            .map(|line| line.chars().map(Token::from_char).collect())
-            .collect();
-
-        walk_tiles(&mut tokens, row_start, col_start, dir);
-        tokens
-            .iter()
-            .flat_map(|line| line.iter().filter(|token| matches!(**token, Token::Seen)))
+        let (mut grid, guard) = parse(input);
+
+        for (pos, _) in walk(&grid, guard) {
+            grid[pos] = Token::Seen;
+        }
+        grid.positions()
+            .filter(|&pos| grid[pos] == Token::Seen)
             .count() as i32
     }
 
     fn part_two(&self, input: &str) -> Self::Output {
-        let (dir, row_start, col_start) = get_start(input);
-        let mut tokens: Vec<Vec<Token>> = input
-            .lines()
-            .map(|line| line.chars().map(Token::from_char).collect())
-            .collect();
-        let rows = tokens.len();
-        let cols = tokens[0].len();
-
-        walk_tiles(&mut tokens, row_start, col_start, dir);
-        let visited: Vec<(usize, usize)> = tokens
-            .iter()
-            .enumerate()
-            .flat_map(|(r, row)| {
-                row.iter().enumerate().filter_map(move |(c, token)| {
-                    if matches!(token, Token::Seen) {
-                        Some((r, c))
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
-
-        let mut result = 0;
-        for &(row, col) in &visited {
-            if (row, col) == (row_start, col_start) {
-                continue;
-            }
+        let (grid, guard) = parse(input);
 
-            let original = std::mem::replace(&mut tokens[row][col], Token::Wall);
-            if !can_escape(&tokens, rows, cols, row_start, col_start, dir) {
-                result += 1;
-            }
+        loop_obstructions(&grid, guard).len() as i32
+    }
+}
 
-            tokens[row][col] = original;
+/// Every cell where a single added obstruction traps the guard in a loop.
+fn loop_obstructions(grid: &Grid<Token>, guard: Guard) -> Vec<(usize, usize)> {
+    let path = walk(grid, guard);
+
+    // an obstruction only changes the route from the first time the guard
+    // would have entered its cell, so each check starts just before it
+    let mut seen = grid.map(|_| false);
+    seen[guard.0] = true;
+    let mut candidates = vec![];
+    for pair in path.windows(2) {
+        let (before, (pos, _)) = (pair[0], pair[1]);
+        if !seen[pos] {
+            seen[pos] = true;
+            candidates.push((before, pos));
         }
+    }
+
+    let table = JumpTable::new(grid);
+    candidates
+        .par_iter()
+        .filter(|&&(before, obstacle)| table.loops(before, obstacle))
+        .map(|&(_, obstacle)| obstacle)
+        .collect()
+}
+
+fn parse(input: &str) -> (Grid<Token>, Guard) {
+    profile::parsing(|| {
+        let chars = Grid::parse(input).expect("lab map is not a rectangular grid");
+        let guard = chars
+            .positions()
+            .find_map(|pos| Some((pos, Direction::from_char(chars[pos])?)))
+            .expect("lab map has no guard");
 
-        result
+        (chars.map(|&ch| Token::from_char(ch)), guard)
+    })
+}
+
+/// Moves the guard one tile forward, or turns right if a wall is in the way.
+fn step(grid: &Grid<Token>, (pos, dir): Guard) -> Option<Guard> {
+    let next = grid.offset(pos, dir.delta())?;
+
+    match grid[next] {
+        Token::Wall => Some((pos, dir.turn_right())),
+        _ => Some((next, dir)),
     }
 }
 
-fn can_escape(
-    matrix: &[Vec<Token>],
-    rows: usize,
-    cols: usize,
-    row: usize,
-    col: usize,
-    dir: Direction,
-) -> bool {
-    let mut visited = vec![0u8; rows * cols];
-    visited[row * cols + col] |= dir.to_bit();
+/// Every state of the guard until it leaves the map.
+fn walk(grid: &Grid<Token>, guard: Guard) -> Vec<Guard> {
+    successors(Some(guard), |&guard| step(grid, guard)).collect()
+}
 
-    let mut current_row = row;
-    let mut current_col = col;
-    let mut current_dir = dir;
+/// Draws the guard's walk in the puzzle's notation: `|` and `-` for cells
+/// crossed vertically or horizontally, `+` where those cross or the guard
+/// turns, and for part two an `O` on every obstruction that would cause a
+/// loop. With `every`, a frame with the guard at its current position is also
+/// taken every that many steps; the finished map is always the last frame.
+pub fn draw_walk(input: &str, part: Part, every: Option<usize>) -> Vec<Grid<char>> {
+    let mut frames = vec![];
+    walk_frames(input, part, every, |frame, _| frames.push(frame));
 
-    loop {
-        let next = current_dir.next_tile(current_row as i32, current_col as i32);
-        let (next_row, next_col) = match conv_usize(next.0, next.1, matrix.len(), matrix[0].len()) {
-            Some(pos) => pos,
-            None => {
-                return true;
-            }
-        };
+    frames
+}
 
-        match matrix[next_row][next_col] {
-            Token::Wall => {
-                current_dir = current_dir.turn_right();
-            }
-            _ => {
-                current_row = next_row;
-                current_col = next_col;
-            }
+/// Like [`draw_walk`], but hands each frame to `emit` as it is drawn along
+/// with the guard's position, which is `None` for the finished map.
+fn walk_frames(
+    input: &str,
+    part: Part,
+    every: Option<usize>,
+    mut emit: impl FnMut(Grid<char>, Option<(usize, usize)>),
+) {
+    let (grid, guard) = parse(input);
+    let mut map = grid.map(|token| match token {
+        Token::Wall => '#',
+        _ => '.',
+    });
+    map[guard.0] = guard.1.to_char();
+
+    for (i, pair) in walk(&grid, guard).windows(2).enumerate() {
+        let ((from, dir), (to, _)) = (pair[0], pair[1]);
+        if every.is_some_and(|every| i % every.max(1) == 0) {
+            let mut frame = map.clone();
+            frame[from] = dir.to_char();
+            emit(frame, Some(from));
         }
 
-        let next_index = next_row * cols + next_col;
-        let bit = current_dir.to_bit();
-        if visited[next_index] & bit != 0 {
-            return false;
+        if from == to {
+            map[from] = trail(map[from], None);
+        } else {
+            map[from] = trail(map[from], Some(dir));
+            map[to] = trail(map[to], Some(dir));
         }
-
-        visited[next_index] |= bit;
     }
-}
-
-fn walk_tiles(matrix: &mut [Vec<Token>], cur_row: usize, cur_col: usize, cur_dir: Direction) {
-    let next = cur_dir.next_tile(cur_row as i32, cur_col as i32);
 
-    if let Some((next_row, next_col)) = conv_usize(next.0, next.1, matrix.len(), matrix[0].len()) {
-        match matrix[next_row][next_col] {
-            Token::Wall => walk_tiles(matrix, cur_row, cur_col, cur_dir.turn_right()),
-            _ => {
-                matrix[next_row][next_col] = Token::Seen;
-                walk_tiles(matrix, next_row, next_col, cur_dir)
-            }
+    if part == Part::Two {
+        for pos in loop_obstructions(&grid, guard) {
+            map[pos] = 'O';
         }
     }
+    emit(map, None);
 }
 
-fn get_start(input: &str) -> (Direction, usize, usize) {
-    let (row_start, col_start, ch) = input
-        .lines()
-        .enumerate()
-        .flat_map(|(row_idx, row)| {
-            row.chars()
-                .enumerate()
-                .map(move |(col_idx, ch)| (row_idx, col_idx, ch))
-        })
-        .find(|&(_, _, c)| Direction::from_char(c).is_some())
-        .unwrap();
-
-    (Direction::from_char(ch).unwrap(), row_start, col_start)
+/// Marks a cell the guard moves through in `dir`, or turns on if `None`. The
+/// starting cell keeps showing where the walk began.
+fn trail(cell: char, dir: Option<Direction>) -> char {
+    match (cell, dir.map(|dir| dir.is_vertical())) {
+        ('^' | '>' | 'v' | '<', _) => cell,
+        ('.' | '|', Some(true)) => '|',
+        ('.' | '-', Some(false)) => '-',
+        _ => '+',
+    }
 }
 
-fn conv_usize(row: i32, col: i32, row_max: usize, col_max: usize) -> Option<(usize, usize)> {
-    if row >= 0 && col >= 0 && row < row_max as i32 && col < col_max as i32 {
-        Some((row as usize, col as usize))
-    } else {
-        None
+/// Plays [`draw_walk`] a step at a time with the guard highlighted, ending on
+/// the finished map with any loop obstructions highlighted.
+impl Visualize for DayStruct {
+    fn visualize(&self, input: &str, part: Part, sink: &mut dyn FrameSink) {
+        let mut step = 0;
+
+        walk_frames(input, part, Some(1), |grid, guard| {
+            let frame = match guard {
+                Some(pos) => {
+                    step += 1;
+                    Frame {
+                        grid,
+                        highlighted: HashSet::from([pos]),
+                        caption: format!("step {}", step),
+                    }
+                }
+                None => {
+                    let visited = grid
+                        .positions()
+                        .filter(|&pos| grid[pos] != '#' && grid[pos] != '.');
+                    let obstructions: HashSet<_> =
+                        grid.positions().filter(|&pos| grid[pos] == 'O').collect();
+                    let caption = match part {
+                        Part::One => format!("{} positions visited", visited.count()),
+                        Part::Two => format!("{} loop obstructions", obstructions.len()),
+                    };
+
+                    Frame {
+                        grid,
+                        highlighted: obstructions,
+                        caption,
+                    }
+                }
+            };
+            sink.emit(frame);
+        });
+    }
+
+    /// Walls, the walked path, the guard and placed obstructions.
+    fn colours(&self) -> ColourMap {
+        ColourMap::default()
+            .with("#", [96, 96, 112])
+            .with("|-+", [64, 160, 255])
+            .with("^>v<", [255, 210, 0])
+            .with("O", [230, 40, 40])
     }
 }
 
 #[cfg(test)]
 mod day6_tests {
     use super::*;
+    use crate::utils::animation::{Capture, Frame, FrameSink};
+    use crate::utils::export::{self, Exporter};
+    use crate::utils::raster::{self, GifWriter};
 
     const EXAMPLE: &str = include_str!("./example.txt");
+    const INPUT: &str = include_str!("./input.txt");
 
     #[test]
     fn test_part_one_example() {
@@ -224,4 +378,148 @@ mod day6_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_jumps_match_single_steps() {
+        let (grid, _) = parse(EXAMPLE);
+        let table = JumpTable::new(&grid);
+        let obstacle = (6, 2);
+
+        for pos in grid.positions().filter(|&pos| grid[pos] != Token::Wall) {
+            for dir in Direction::ALL {
+                let mut blocked = grid.clone();
+                blocked[obstacle] = Token::Wall;
+                let expected = successors(Some((pos, dir)), |&guard| step(&blocked, guard))
+                    .skip(1)
+                    .find(|&(_, facing)| facing != dir)
+                    .map(|(stop, _)| stop);
+
+                if pos != obstacle {
+                    assert_eq!(table.jump(pos, dir, obstacle), expected, "{:?}", pos);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_part_two_matches_brute_force() {
+        let maps = [
+            EXAMPLE,
+            ".#..\n...#\n.^..\n..#.",
+            ".##..\n....#\n.....\n.^...\n...#.",
+        ];
+
+        for map in maps {
+            let (grid, guard) = parse(map);
+            let brute_force = grid
+                .positions()
+                .filter(|&pos| pos != guard.0 && grid[pos] != Token::Wall)
+                .filter(|&pos| {
+                    let mut blocked = grid.clone();
+                    blocked[pos] = Token::Wall;
+                    cycle::brent(guard, |&guard| step(&blocked, guard)).is_some()
+                })
+                .count() as i32;
+
+            assert_eq!(DayStruct.part_two(map), brute_force, "{}", map);
+        }
+    }
+
+    #[test]
+    fn test_draw_walk() {
+        let frames = draw_walk(EXAMPLE, Part::Two, Some(5));
+        let render = |frame: &Grid<char>| frame.to_string();
+
+        assert_eq!(frames.len(), 53 / 5 + 2);
+        assert_eq!(render(&frames[3]).lines().nth(5), Some("....|...v."));
+        assert_eq!(
+            render(frames.last().unwrap()),
+            "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+O^-+-+.
+.+----OO#.
+#O-O--+|..
+......#O..
+"
+        );
+    }
+
+    #[test]
+    fn test_visualize_highlights_guard() {
+        let mut capture = Capture::default();
+        DayStruct.visualize(EXAMPLE, Part::One, &mut capture);
+
+        assert_eq!(capture.frames.len(), 55);
+        assert_eq!(capture.frames[0].highlighted, HashSet::from([(6, 4)]));
+        assert_eq!(capture.frames[53].caption, "step 54");
+        assert_eq!(capture.frames[54].caption, "41 positions visited");
+
+        let mut capture = Capture::default();
+        DayStruct.visualize(EXAMPLE, Part::Two, &mut capture);
+        let last = capture.frames.last().unwrap();
+
+        assert_eq!(last.caption, "6 loop obstructions");
+        assert!(last.highlighted.contains(&(6, 3)));
+    }
+
+    fn export_gif(input: &str, part: Part, step: usize, cell_size: usize) -> (Vec<u8>, usize) {
+        let colours = DayStruct.colours();
+        let mut gif = None;
+        let mut exporter = Exporter::new(step, |frame| {
+            let image = export::image(&frame, cell_size, &colours);
+            let gif = match &mut gif {
+                Some(gif) => gif,
+                None => gif.insert(GifWriter::new(
+                    vec![],
+                    image.width(),
+                    image.height(),
+                    &colours.palette(),
+                    4,
+                )?),
+            };
+            gif.write(&image)
+        });
+        DayStruct.visualize(input, part, &mut exporter);
+        exporter.finish();
+        let written = exporter.result().unwrap();
+
+        (gif.unwrap().into_inner().unwrap(), written)
+    }
+
+    #[test]
+    fn test_gif_export_matches_golden() {
+        let (gif, _) = export_gif(EXAMPLE, Part::Two, 10, 2);
+        let golden = include_bytes!("./example_part_two.gif");
+
+        assert_eq!(
+            raster::read_gif(gif.as_slice()).unwrap(),
+            raster::read_gif(golden.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_input_exports_with_step() {
+        struct Count(usize);
+        impl FrameSink for Count {
+            fn emit(&mut self, _: Frame) {
+                self.0 += 1;
+            }
+        }
+
+        let mut count = Count(0);
+        DayStruct.visualize(INPUT, Part::One, &mut count);
+        let last = count.0 - 1;
+        let (gif, written) = export_gif(INPUT, Part::One, 500, 1);
+
+        assert_eq!(
+            written,
+            last / 500 + 1 + usize::from(!last.is_multiple_of(500))
+        );
+        assert_eq!(raster::read_gif(gif.as_slice()).unwrap().len(), written);
+    }
 }
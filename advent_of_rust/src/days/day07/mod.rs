@@ -1,105 +1,464 @@
-use super::Day;
+use super::{Day, Explain, Part};
+use crate::profile;
+use crate::utils::operator_search::{BinaryOperator, Operand, OperatorSearch, Strategy};
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+use std::fmt::Write;
+use std::str::FromStr;
 
-enum Operator {
+const PART_ONE: [Operator; 2] = [Operator::Add, Operator::Multiply];
+const PART_TWO: [Operator; 3] = [Operator::Add, Operator::Multiply, Operator::Concat];
+
+pub enum Operator {
     Multiply,
     Add,
     Concat,
 }
 
-impl Operator {
-    fn execute(&self, l: i64, r: i64) -> i64 {
+/// Integer types an equation can be solved in. Lines are solved in `i64`
+/// when every number fits, falling back to `i128` and then [`BigInt`].
+pub trait Calibration:
+    Operand + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + From<u8> + FromStr + Into<BigInt>
+{
+}
+
+impl<N> Calibration for N where
+    N: Operand
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + From<u8>
+        + FromStr
+        + Into<BigInt>
+{
+}
+
+impl<N: Calibration> BinaryOperator<N> for Operator {
+    fn apply(&self, l: &N, r: &N) -> Option<N> {
         match self {
-            Operator::Multiply => l * r,
-            Operator::Add => l + r,
-            Operator::Concat => format!("{}{}", l, r)
-                .parse::<i64>()
-                .expect("concatenated value exceeds i64"),
+            Operator::Multiply => l.checked_mul(r),
+            Operator::Add => l.checked_add(r),
+            Operator::Concat => l.checked_mul(&digit_shift(r)?)?.checked_add(r),
         }
     }
+
+    fn invert(&self, result: &N, r: &N) -> Option<N> {
+        match self {
+            Operator::Multiply => {
+                let l = result.checked_div(r)?;
+                (l.checked_mul(r)? == *result).then_some(l)
+            }
+            Operator::Add => (result >= r).then(|| result.checked_sub(r))?,
+            Operator::Concat => {
+                let shift = digit_shift(r)?;
+                let l = result.checked_div(&shift)?;
+                (result >= r && l.checked_mul(&shift)?.checked_add(r)? == *result).then_some(l)
+            }
+        }
+    }
+
+    fn absorbs(&self, result: &N, r: &N) -> bool {
+        let zero = N::from(0);
+        matches!(self, Operator::Multiply) && *r == zero && *result == zero
+    }
+
+    fn symbol(&self) -> &str {
+        match self {
+            Operator::Multiply => "*",
+            Operator::Add => "+",
+            Operator::Concat => "||",
+        }
+    }
+
+    fn never_decreases(&self, r: &N) -> bool {
+        match self {
+            Operator::Multiply => *r >= N::from(1),
+            Operator::Add | Operator::Concat => *r >= N::from(0),
+        }
+    }
+}
+
+/// `10^digits(n)`, i.e. the factor that makes room for `n` when concatenating.
+fn digit_shift<N: Calibration>(n: &N) -> Option<N> {
+    let ten = N::from(10);
+    let mut shift = ten.clone();
+    while shift <= *n {
+        shift = shift.checked_mul(&ten)?;
+    }
+
+    Some(shift)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evaluation {
+    Sequential,
+    Parallel,
 }
 
 pub struct DayStruct;
 impl Day for DayStruct {
-    type Output = i64;
+    type Output = BigInt;
 
     fn part_one(&self, input: &str) -> Self::Output {
-        solve(input, &[Operator::Add, Operator::Multiply])
+        solve(input, &PART_ONE, Strategy::Backward, Evaluation::Parallel)
     }
 
     fn part_two(&self, input: &str) -> Self::Output {
-        solve(
-            input,
-            &[Operator::Add, Operator::Multiply, Operator::Concat],
+        solve(input, &PART_TWO, Strategy::Backward, Evaluation::Parallel)
+    }
+}
+
+pub fn solve(
+    input: &str,
+    operators: &[Operator],
+    strategy: Strategy,
+    evaluation: Evaluation,
+) -> BigInt {
+    let search = OperatorSearch::new(operators, strategy);
+    let calibrate = |l: &str| calibrate(&search, l).unwrap_or_default();
+
+    match evaluation {
+        Evaluation::Sequential => input.lines().map(calibrate).sum(),
+        Evaluation::Parallel => input.par_lines().map(calibrate).sum(),
+    }
+}
+
+/// The line's target if it is solvable, solved in the narrowest type that
+/// fits it (see [`parse_narrow`]).
+fn calibrate(search: &OperatorSearch<Operator>, l: &str) -> Option<BigInt> {
+    fn solved<N: Calibration>(
+        search: &OperatorSearch<Operator>,
+        (goal, nums): (N, Vec<N>),
+    ) -> Option<BigInt> {
+        search.is_solvable(&goal, &nums).then(|| goal.into())
+    }
+
+    if let Some(line) = parse_narrow::<i64>(l) {
+        solved(search, line)
+    } else if let Some(line) = parse_narrow::<i128>(l) {
+        solved(search, line)
+    } else {
+        solved(
+            search,
+            parse_line::<BigInt>(l).expect("non integer in input line"),
         )
     }
 }
 
-fn solve(input: &str, operands: &[Operator]) -> i64 {
-    input
-        .lines()
-        .map(|l| {
-            let (goal_str, nums_str) = l
-                .split_once(':')
-                .expect("malformed line without ':' in input str");
-            let goal = goal_str.parse::<i64>().expect("non i64 in input goal");
-            let nums: Vec<i64> = nums_str
-                .split_whitespace()
-                .map(|n| n.parse::<i64>().expect("non i64 in input operands"))
-                .collect();
-
-            if has_solution(goal, &nums, 1, nums[0], operands) {
-                goal
-            } else {
-                0
+#[derive(Serialize)]
+pub struct LineExplanation {
+    pub line: usize,
+    #[serde(serialize_with = "decimal")]
+    pub target: BigInt,
+    #[serde(serialize_with = "decimals")]
+    pub operands: Vec<BigInt>,
+    pub solvable: bool,
+    /// One solution, e.g. `292 = 11 + 6 * 16 + 20`
+    pub witness: Option<String>,
+    pub solutions: u64,
+    /// Only solvable with `||`, so it counts in part two but not part one
+    pub needs_concat: bool,
+}
+
+/// One record per calibration line: whether it is solvable, a witnessing
+/// equation, how many operator assignments solve it and whether it needs
+/// concatenation.
+impl Explain for DayStruct {
+    type Record = LineExplanation;
+
+    fn explain(&self, input: &str, part: Part) -> Vec<LineExplanation> {
+        let operators: &[Operator] = match part {
+            Part::One => &PART_ONE,
+            Part::Two => &PART_TWO,
+        };
+        let search = OperatorSearch::new(operators, Strategy::Backward);
+
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                if let Some(line) = parse_narrow::<i64>(l) {
+                    explain_line(i + 1, line, &search, part)
+                } else if let Some(line) = parse_narrow::<i128>(l) {
+                    explain_line(i + 1, line, &search, part)
+                } else {
+                    let line = parse_line::<BigInt>(l).expect("non integer in input line");
+                    explain_line(i + 1, line, &search, part)
+                }
+            })
+            .collect()
+    }
+
+    fn describe(&self, record: &LineExplanation, _part: Part) -> String {
+        describe(record)
+    }
+}
+
+fn explain_line<N: Calibration>(
+    line: usize,
+    (target, operands): (N, Vec<N>),
+    search: &OperatorSearch<Operator>,
+    part: Part,
+) -> LineExplanation {
+    let witness = search.find(&target, &operands);
+    let solvable = witness.is_some();
+    let without_concat = OperatorSearch::new(&PART_ONE, Strategy::Backward);
+
+    LineExplanation {
+        line,
+        target: target.clone().into(),
+        operands: operands.iter().cloned().map(Into::into).collect(),
+        solvable,
+        witness: witness.map(|expression| format!("{} = {}", target, expression)),
+        solutions: search.count(&target, &operands),
+        needs_concat: part == Part::Two
+            && solvable
+            && !without_concat.is_solvable(&target, &operands),
+    }
+}
+
+/// Writes `n` as a JSON number, or as a string of its digits if it is too
+/// big for one.
+fn decimal<S: Serializer>(n: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+    match i128::try_from(n) {
+        Ok(n) => serializer.serialize_i128(n),
+        Err(_) => serializer.serialize_str(&n.to_string()),
+    }
+}
+
+fn decimals<S: Serializer>(ns: &[BigInt], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(ns.iter().map(Decimal))
+}
+
+struct Decimal<'a>(&'a BigInt);
+
+impl Serialize for Decimal<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        decimal(self.0, serializer)
+    }
+}
+
+fn describe(explanation: &LineExplanation) -> String {
+    let operands: Vec<String> = explanation.operands.iter().map(BigInt::to_string).collect();
+    let mut text = format!(
+        "line {}: {}: {}",
+        explanation.line,
+        explanation.target,
+        operands.join(" ")
+    );
+
+    match &explanation.witness {
+        Some(witness) => {
+            write!(
+                text,
+                " -> {} ({} solution{}",
+                witness,
+                explanation.solutions,
+                if explanation.solutions == 1 { "" } else { "s" }
+            )
+            .unwrap();
+            if explanation.needs_concat {
+                text.push_str(", needs ||");
             }
-        })
-        .sum()
-}
-
-fn has_solution(
-    goal: i64,
-    nums: &[i64],
-    index: usize,
-    current: i64,
-    operands: &[Operator],
-) -> bool {
-    if index == nums.len() {
-        return current == goal;
-    }
-
-    let next_num = nums[index];
-    for operand in operands {
-        if has_solution(
-            goal,
-            nums,
-            index + 1,
-            operand.execute(current, next_num),
-            operands,
-        ) {
-            return true;
+            text.push(')');
         }
+        None => text.push_str(" -> unsolvable"),
     }
 
-    false
+    text
+}
+
+/// Parses a line to be solved in `N`, or returns `None` if it needs a wider
+/// type. With positive operands every operator only grows the value, so a
+/// result that overflows `N` has already passed the target and is a dead
+/// end. A zero or negative operand can bring such a result back in range, so
+/// those lines are left to [`BigInt`], which can't overflow.
+fn parse_narrow<N: Calibration>(l: &str) -> Option<(N, Vec<N>)> {
+    parse_line::<N>(l).filter(|(_, nums)| nums.iter().all(|n| *n > N::from(0)))
+}
+
+/// Parses `goal: nums...`, or returns `None` if a number doesn't fit `N`.
+fn parse_line<N: FromStr>(l: &str) -> Option<(N, Vec<N>)> {
+    profile::parsing(|| {
+        let (goal_str, nums_str) = l
+            .split_once(':')
+            .expect("malformed line without ':' in input str");
+        let goal = goal_str.trim().parse::<N>().ok()?;
+        let nums = nums_str
+            .split_whitespace()
+            .map(|n| n.parse::<N>().ok())
+            .collect::<Option<Vec<N>>>()?;
+
+        Some((goal, nums))
+    })
 }
 
 #[cfg(test)]
 mod day7_tests {
     use super::*;
+    use crate::days::{explain, ExplainFormat};
 
     const EXAMPLE: &str = include_str!("./example.txt");
 
     #[test]
     fn test_part_one_example() {
-        let expected = 3749;
+        let expected = BigInt::from(3749);
 
         assert_eq!(DayStruct.part_one(EXAMPLE), expected);
     }
 
     #[test]
     fn test_part_two_example() {
-        let expected = 11387;
+        let expected = BigInt::from(11387);
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_explain_example() {
+        let text = explain(&DayStruct, EXAMPLE, Part::Two, ExplainFormat::Text);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[1],
+            "line 2: 3267: 81 40 27 -> 3267 = 81 * 40 + 27 (2 solutions)"
+        );
+        assert_eq!(lines[2], "line 3: 83: 17 5 -> unsolvable");
+        assert_eq!(
+            lines[3],
+            "line 4: 156: 15 6 -> 156 = 15 || 6 (1 solution, needs ||)"
+        );
+        assert_eq!(lines[9], "answer: 11387");
+
+        let json = explain(&DayStruct, EXAMPLE, Part::One, ExplainFormat::Json);
+        assert_eq!(
+            json.lines().last().unwrap(),
+            r#"{"line":9,"target":292,"operands":[11,6,16,20],"solvable":true,"witness":"292 = 11 + 6 * 16 + 20","solutions":1,"needs_concat":false}"#
+        );
+    }
+
+    #[test]
+    fn test_explain_json_writes_wide_numbers() {
+        let input = format!("{0}0: {0} 0\n1{0}: 1 {0}", u128::MAX);
+        let json = explain(&DayStruct, &input, Part::Two, ExplainFormat::Json);
+        let lines: Vec<&str> = json.lines().collect();
+
+        assert!(lines[0].starts_with(&format!(
+            r#"{{"line":1,"target":"{0}0","operands":["{0}",0],"solvable":true"#,
+            u128::MAX
+        )));
+        assert!(lines[1].contains(&format!(r#""operands":[1,"{}"]"#, u128::MAX)));
+        let line = format!("{}: {} 1", i128::MAX, i128::MAX - 1);
+        let json = explain(&DayStruct, &line, Part::One, ExplainFormat::Json);
+        assert!(json.starts_with(&format!(
+            r#"{{"line":1,"target":{},"operands":[{},1]"#,
+            i128::MAX,
+            i128::MAX - 1
+        )));
+    }
+
+    #[test]
+    fn test_concat_overflow_is_unsolvable() {
+        let input = format!("{}: {} 10", i64::MAX, i64::MAX / 10);
+
+        assert_eq!(DayStruct.part_two(&input), BigInt::default());
+    }
+
+    /// Solvable lines built from random six digit operands and operators, so
+    /// most targets overflow `i64` and many overflow `i128` as well.
+    fn overflowing_lines(count: usize, seed: u64) -> Vec<(String, BigInt)> {
+        let mut state = seed;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+
+        (0..count)
+            .map(|_| {
+                let operands: Vec<BigInt> = (0..8)
+                    .map(|_| BigInt::from(100_000 + next(900_000)))
+                    .collect();
+                let target = operands[1..].iter().fold(operands[0].clone(), |acc, n| {
+                    PART_TWO[next(3) as usize].apply(&acc, n).unwrap()
+                });
+                let operands: Vec<String> = operands.iter().map(BigInt::to_string).collect();
+
+                (format!("{}: {}", target, operands.join(" ")), target)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_overflowing_lines_fall_back_to_wider_types() {
+        let lines = overflowing_lines(20, 7);
+        let beyond = |limit: BigInt| lines.iter().filter(|(_, target)| *target > limit).count();
+        assert!(beyond(i64::MAX.into()) > beyond(i128::MAX.into()));
+        assert!(beyond(i128::MAX.into()) > 0);
+
+        let input: Vec<&str> = lines.iter().map(|(line, _)| line.as_str()).collect();
+        let input = input.join("\n");
+        let expected: BigInt = lines.iter().map(|(_, target)| target).sum();
+
+        assert_eq!(DayStruct.part_two(&input), expected);
+        assert_eq!(
+            solve(&input, &PART_TWO, Strategy::Forward, Evaluation::Sequential),
+            expected
+        );
+        let unsolvable = format!("{}: {} 1", u128::MAX, u64::MAX);
+        assert_eq!(DayStruct.part_two(&unsolvable), BigInt::default());
+
+        // Multiplying by zero brings an overflowing prefix back in range
+        for line in [
+            format!("5: {} 2 0 5", i64::MAX),
+            format!("5: {} 2 0 5", i128::MAX),
+            format!("5: {}0 2 0 5", i64::MAX),
+        ] {
+            assert_eq!(DayStruct.part_one(&line), BigInt::from(5), "{}", line);
+            let text = explain(&DayStruct, &line, Part::One, ExplainFormat::Text);
+            assert!(text.contains("(2 solutions)"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let operators = [Operator::Add, Operator::Multiply, Operator::Concat];
+        let forward = solve(
+            EXAMPLE,
+            &operators,
+            Strategy::Forward,
+            Evaluation::Sequential,
+        );
+        let backward = solve(
+            EXAMPLE,
+            &operators,
+            Strategy::Backward,
+            Evaluation::Sequential,
+        );
+
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn test_zero_operands() {
+        let input = "10: 5 0 10\n0: 7 3 0\n5: 0 5\n12: 3 0 4\n30: 3 0";
+
+        for operators in [&PART_ONE[..], &PART_TWO[..]] {
+            let forward = solve(input, operators, Strategy::Forward, Evaluation::Sequential);
+            let backward = solve(input, operators, Strategy::Backward, Evaluation::Sequential);
+            assert_eq!(forward, backward);
+        }
+        assert_eq!(DayStruct.part_one(input), BigInt::from(27));
+        assert_eq!(DayStruct.part_two(input), BigInt::from(57));
+
+        let text = explain(&DayStruct, "0: 7 3 0", Part::One, ExplainFormat::Text);
+        assert_eq!(
+            text.lines().next().unwrap(),
+            "line 1: 0: 7 3 0 -> 0 = 7 + 3 * 0 (2 solutions)"
+        );
+    }
 }
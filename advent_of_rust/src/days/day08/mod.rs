@@ -1,119 +1,291 @@
-use super::Day;
-use std::collections::{HashMap, HashSet};
+use super::{Day, Part, Visualize};
+use crate::profile;
+use crate::utils::animation::{Frame, FrameSink};
+use crate::utils::export::ColourMap;
+use crate::utils::grid::{Grid, GridError, Highlight};
+use crate::utils::svg;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashSet};
 
-pub struct DayStruct;
-impl Day for DayStruct {
-    type Output = i32;
+/// Antinode cells by `(row, col)`, sorted, for each antenna frequency.
+pub type Antinodes = BTreeMap<char, Vec<(usize, usize)>>;
 
-    fn part_one(&self, input: &str) -> Self::Output {
-        let mut map: HashMap<char, Vec<(i32, i32)>> = HashMap::new();
-        let mut set: HashSet<(i32, i32)> = HashSet::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RenderFormat {
+    /// `#` on every antinode that isn't covered by an antenna, as the puzzle
+    /// draws it
+    Plain,
+    /// Like plain, with every antinode cell coloured
+    Ansi,
+    Svg,
+}
 
-        let lines: Vec<&str> = input.lines().collect();
-        let max_y = lines.len() - 1;
-        let max_x = lines[max_y].len() - 1;
+/// Where a pair of same-frequency antennas `a` and `b` produces antinodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AntinodeRule {
+    /// Points on the line where one antenna is this many times as far away
+    /// as the other, beyond the nearer one. Part one uses a ratio of 2.
+    Ratio(u32),
+    /// Every grid point on the line through both antennas (part two).
+    Collinear,
+    /// The points `a + k * (b - a)` and `b + k * (a - b)` for each `k`, so
+    /// the order of the antennas doesn't matter; `[-1, 2]` is part one.
+    Harmonics(Vec<i64>),
+}
 
-        for (y, line) in lines.into_iter().enumerate() {
-            for (x, char) in line.chars().enumerate() {
-                if char != '.' {
-                    map.entry(char).or_default().push((x as i32, y as i32));
-                }
-            }
+impl AntinodeRule {
+    pub fn for_part(part: Part) -> AntinodeRule {
+        match part {
+            Part::One => AntinodeRule::Ratio(2),
+            Part::Two => AntinodeRule::Collinear,
         }
+    }
+}
 
-        for antenna_coordinates in map.values() {
-            for (i, coord_one) in antenna_coordinates.iter().enumerate() {
-                for coord_two in antenna_coordinates[i + 1..].iter() {
-                    let diff = abs_diff(coord_one, coord_two);
-                    let antinode_one = compute_antinode(coord_one, coord_two, diff);
-                    let antinode_two = compute_antinode(coord_two, coord_one, diff);
+/// Antenna positions by `(row, col)`, grouped by frequency, on a map of the
+/// given size. Only the antennas are stored, so maps can be arbitrarily
+/// large as long as they are sparse.
+pub struct AntennaMap {
+    width: i64,
+    height: i64,
+    frequencies: BTreeMap<char, Vec<(i64, i64)>>,
+}
 
-                    if coordinate_within_bounds(&antinode_one, max_x, max_y) {
-                        set.insert(antinode_one);
-                    }
-                    if coordinate_within_bounds(&antinode_two, max_x, max_y) {
-                        set.insert(antinode_two);
+impl AntennaMap {
+    pub fn parse(input: &str) -> Result<Self, GridError> {
+        profile::parsing(|| {
+            let grid = Grid::parse(input)?;
+            let antennas = grid
+                .positions()
+                .filter(|&pos| grid[pos] != '.')
+                .map(|pos| (grid[pos], pos));
+
+            Ok(AntennaMap::from_antennas(
+                grid.width(),
+                grid.height(),
+                antennas,
+            ))
+        })
+    }
+
+    /// Builds a map directly from `(frequency, (row, col))` pairs.
+    pub fn from_antennas(
+        width: usize,
+        height: usize,
+        antennas: impl IntoIterator<Item = (char, (usize, usize))>,
+    ) -> Self {
+        let mut frequencies: BTreeMap<char, Vec<(i64, i64)>> = BTreeMap::new();
+        for (frequency, (row, col)) in antennas {
+            assert!(row < height && col < width, "antenna outside the map");
+            frequencies
+                .entry(frequency)
+                .or_default()
+                .push((row as i64, col as i64));
+        }
+
+        AntennaMap {
+            width: width as i64,
+            height: height as i64,
+            frequencies,
+        }
+    }
+
+    pub fn antinodes(&self, rule: &AntinodeRule) -> Antinodes {
+        self.frequencies
+            .iter()
+            .map(|(&frequency, antennas)| {
+                let mut set: HashSet<(i64, i64)> = HashSet::new();
+                for (i, &a) in antennas.iter().enumerate() {
+                    for &b in &antennas[i + 1..] {
+                        self.pair_antinodes(a, b, rule, &mut set);
                     }
                 }
-            }
-        }
 
-        set.len() as i32
+                let mut cells: Vec<(usize, usize)> = set
+                    .into_iter()
+                    .map(|(row, col)| (row as usize, col as usize))
+                    .collect();
+                cells.sort_unstable();
+                (frequency, cells)
+            })
+            .collect()
     }
 
-    fn part_two(&self, input: &str) -> Self::Output {
-        let mut map: HashMap<char, Vec<(i32, i32)>> = HashMap::new();
-        let mut set: HashSet<(i32, i32)> = HashSet::new();
+    fn pair_antinodes(
+        &self,
+        a: (i64, i64),
+        b: (i64, i64),
+        rule: &AntinodeRule,
+        set: &mut HashSet<(i64, i64)>,
+    ) {
+        // Antennas sharing a cell don't define a line
+        if a == b {
+            return;
+        }
 
-        let lines: Vec<&str> = input.lines().collect();
-        let max_y = lines.len() - 1;
-        let max_x = lines[max_y].len() - 1;
+        // `None` if the point is so far out that it overflows, so off the map
+        let along = |from: (i64, i64), k: i64, step: (i64, i64)| {
+            Some((
+                from.0.checked_add(k.checked_mul(step.0)?)?,
+                from.1.checked_add(k.checked_mul(step.1)?)?,
+            ))
+        };
+        let diff = (b.0 - a.0, b.1 - a.1);
 
-        for (y, line) in lines.into_iter().enumerate() {
-            for (x, char) in line.chars().enumerate() {
-                if char != '.' {
-                    map.entry(char).or_default().push((x as i32, y as i32));
+        match rule {
+            AntinodeRule::Ratio(ratio) => {
+                // p - near = (p - far) / ratio, so p = (ratio * near - far) / (ratio - 1)
+                let ratio = *ratio as i64;
+                if ratio < 2 {
+                    return;
                 }
-            }
-        }
 
-        for antenna_coordinates in map.values() {
-            for (i, coord_one) in antenna_coordinates.iter().enumerate() {
-                if antenna_coordinates.len() > 1 {
-                    set.insert(*coord_one);
+                let scaled = |near: i64, far: i64| ratio.checked_mul(near)?.checked_sub(far);
+                for (near, far) in [(a, b), (b, a)] {
+                    let (Some(row), Some(col)) = (scaled(near.0, far.0), scaled(near.1, far.1))
+                    else {
+                        continue;
+                    };
+                    if row % (ratio - 1) == 0 && col % (ratio - 1) == 0 {
+                        let antinode = (row / (ratio - 1), col / (ratio - 1));
+                        if self.contains(antinode) {
+                            set.insert(antinode);
+                        }
+                    }
                 }
+            }
+            AntinodeRule::Collinear => {
+                let divisor = gcd(diff.0, diff.1);
+                let step = (diff.0 / divisor, diff.1 / divisor);
 
-                for coord_two in antenna_coordinates[i + 1..].iter() {
-                    let diff = abs_diff(coord_one, coord_two);
-                    recursively_push_antinode_in_direction(
-                        &mut set, coord_one, coord_two, diff, max_x, max_y,
-                    );
-                    recursively_push_antinode_in_direction(
-                        &mut set, coord_two, coord_one, diff, max_x, max_y,
+                for direction in [1, -1] {
+                    let mut k = 0;
+                    while let Some(antinode) = along(a, k, step).filter(|&p| self.contains(p)) {
+                        set.insert(antinode);
+                        k += direction;
+                    }
+                }
+            }
+            AntinodeRule::Harmonics(multiples) => {
+                for (from, step) in [(a, diff), (b, (-diff.0, -diff.1))] {
+                    set.extend(
+                        multiples
+                            .iter()
+                            .filter_map(|&k| along(from, k, step))
+                            .filter(|&antinode| self.contains(antinode)),
                     );
                 }
             }
         }
+    }
+
+    fn contains(&self, (row, col): (i64, i64)) -> bool {
+        (0..self.height).contains(&row) && (0..self.width).contains(&col)
+    }
 
-        set.len() as i32
+    /// Redraws the map with the antinodes overlaid. Antennas stay visible
+    /// where they coincide with an antinode, as in the puzzle's diagrams.
+    pub fn render(&self, antinodes: &Antinodes, format: RenderFormat) -> String {
+        let cells: HashSet<(usize, usize)> = antinodes.values().flatten().copied().collect();
+        let overlay = self.overlay(&cells);
+
+        match format {
+            RenderFormat::Plain => overlay.to_string(),
+            RenderFormat::Ansi => overlay.render(&cells, Highlight::Ansi),
+            RenderFormat::Svg => svg::from_grid(&overlay, 16, |pos| {
+                cells.contains(&pos).then_some("#8b1a1a")
+            }),
+        }
+    }
+
+    /// The map with `#` on every antinode cell not covered by an antenna.
+    fn overlay(&self, cells: &HashSet<(usize, usize)>) -> Grid<char> {
+        let mut overlay = Grid::from_fn(self.height as usize, self.width as usize, |pos| {
+            if cells.contains(&pos) {
+                '#'
+            } else {
+                '.'
+            }
+        });
+        for (&frequency, antennas) in &self.frequencies {
+            for &(row, col) in antennas {
+                overlay[(row as usize, col as usize)] = frequency;
+            }
+        }
+
+        overlay
     }
 }
 
-fn compute_antinode(coord: &(i32, i32), other: &(i32, i32), diff: (i32, i32)) -> (i32, i32) {
-    let x = coord.0 + (diff.0 * if coord.0 > other.0 { 1 } else { -1 });
-    let y = coord.1 + (diff.1 * if coord.1 > other.1 { 1 } else { -1 });
-    (x, y)
+pub struct DayStruct;
+impl Day for DayStruct {
+    type Output = i32;
+
+    fn part_one(&self, input: &str) -> Self::Output {
+        count_antinodes(input, Part::One)
+    }
+
+    fn part_two(&self, input: &str) -> Self::Output {
+        count_antinodes(input, Part::Two)
+    }
 }
 
-fn coordinate_within_bounds(coord: &(i32, i32), max_x: usize, max_y: usize) -> bool {
-    coord.0 >= 0 && coord.0 <= max_x as i32 && coord.1 >= 0 && coord.1 <= max_y as i32
+/// Adds one frequency's antinodes at a time, highlighting that frequency's
+/// antennas and antinodes.
+impl Visualize for DayStruct {
+    fn visualize(&self, input: &str, part: Part, sink: &mut dyn FrameSink) {
+        let map = AntennaMap::parse(input).expect("antenna map is not a rectangular grid");
+        let mut cells = HashSet::new();
+
+        for (frequency, antinodes) in map.antinodes(&AntinodeRule::for_part(part)) {
+            cells.extend(antinodes.iter().copied());
+            let antennas = map.frequencies[&frequency]
+                .iter()
+                .map(|&(row, col)| (row as usize, col as usize));
+
+            sink.emit(Frame {
+                grid: map.overlay(&cells),
+                highlighted: antennas.chain(antinodes.iter().copied()).collect(),
+                caption: format!(
+                    "frequency {}: {} antinodes, {} in total",
+                    frequency,
+                    antinodes.len(),
+                    cells.len()
+                ),
+            });
+        }
+    }
+
+    /// `#` marks an antinode.
+    fn colours(&self) -> ColourMap {
+        ColourMap::default().with("#", [230, 40, 40])
+    }
 }
 
-fn abs_diff(coord_one: &(i32, i32), coord_two: &(i32, i32)) -> (i32, i32) {
-    (
-        coord_one.0.abs_diff(coord_two.0) as i32,
-        coord_one.1.abs_diff(coord_two.1) as i32,
-    )
+fn count_antinodes(input: &str, part: Part) -> i32 {
+    let map = AntennaMap::parse(input).expect("antenna map is not a rectangular grid");
+    let cells: HashSet<(usize, usize)> = map
+        .antinodes(&AntinodeRule::for_part(part))
+        .into_values()
+        .flatten()
+        .collect();
+
+    cells.len() as i32
 }
 
-fn recursively_push_antinode_in_direction(
-    set: &mut HashSet<(i32, i32)>,
-    coord_one: &(i32, i32),
-    coord_two: &(i32, i32),
-    diff: (i32, i32),
-    max_x: usize,
-    max_y: usize,
-) {
-    let antinode = compute_antinode(coord_one, coord_two, diff);
-    if coordinate_within_bounds(&antinode, max_x, max_y) {
-        set.insert(antinode);
-        recursively_push_antinode_in_direction(set, &antinode, coord_one, diff, max_x, max_y);
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
     }
 }
 
 #[cfg(test)]
 mod day8_tests {
     use super::*;
+    use crate::utils::animation::Capture;
+    use crate::utils::export;
 
     const EXAMPLE: &str = include_str!("./example.txt");
 
@@ -130,4 +302,146 @@ mod day8_tests {
 
         assert_eq!(DayStruct.part_two(EXAMPLE), expected);
     }
+
+    #[test]
+    fn test_bounds_ignore_trailing_whitespace() {
+        let padded: String = EXAMPLE
+            .lines()
+            .map(|line| format!("{}  \n", line))
+            .collect();
+
+        assert_eq!(DayStruct.part_one(&padded), 14);
+        assert!(AntennaMap::parse("..a.\n..a\n....").is_err());
+    }
+
+    #[test]
+    fn test_antinodes_per_frequency() {
+        let map = AntennaMap::parse(EXAMPLE).unwrap();
+        let antinodes = map.antinodes(&AntinodeRule::for_part(Part::One));
+
+        assert_eq!(antinodes.keys().collect::<Vec<_>>(), vec![&'0', &'A']);
+        assert_eq!(
+            antinodes[&'A'],
+            vec![(1, 3), (2, 4), (7, 7), (10, 10), (11, 10)]
+        );
+    }
+
+    #[test]
+    fn test_render_matches_puzzle_diagram() {
+        let map = AntennaMap::parse(EXAMPLE).unwrap();
+        let expected = "\
+##....#....#
+.#.#....0...
+..#.#0....#.
+..##...0....
+....0....#..
+.#...#A....#
+...#..#.....
+#....#.#....
+..#.....A...
+....#....A..
+.#........#.
+...#......##
+";
+
+        assert_eq!(
+            map.render(
+                &map.antinodes(&AntinodeRule::for_part(Part::Two)),
+                RenderFormat::Plain
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rules_agree_on_part_one() {
+        let map = AntennaMap::parse(EXAMPLE).unwrap();
+
+        assert_eq!(
+            map.antinodes(&AntinodeRule::Ratio(2)),
+            map.antinodes(&AntinodeRule::Harmonics(vec![-1, 2]))
+        );
+    }
+
+    #[test]
+    fn test_collinear_uses_reduced_steps() {
+        let map = AntennaMap::from_antennas(5, 5, [('a', (0, 0)), ('a', (2, 4))]);
+        let antinodes = map.antinodes(&AntinodeRule::Collinear);
+
+        assert_eq!(antinodes[&'a'], vec![(0, 0), (1, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn test_shared_cells_and_huge_multiples() {
+        let map = AntennaMap::from_antennas(4, 4, [('a', (1, 1)), ('a', (1, 1)), ('a', (2, 2))]);
+
+        assert_eq!(
+            map.antinodes(&AntinodeRule::Collinear)[&'a'],
+            vec![(0, 0), (1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(
+            map.antinodes(&AntinodeRule::Ratio(2))[&'a'],
+            vec![(0, 0), (3, 3)]
+        );
+        assert_eq!(
+            map.antinodes(&AntinodeRule::Harmonics(vec![i64::MIN, -1, 2, i64::MAX]))[&'a'],
+            vec![(0, 0), (3, 3)]
+        );
+        assert!(map.antinodes(&AntinodeRule::Ratio(u32::MAX))[&'a'].is_empty());
+    }
+
+    #[test]
+    fn test_harmonics_ignore_antenna_order() {
+        let rule = AntinodeRule::Harmonics(vec![2]);
+        let forward = AntennaMap::from_antennas(6, 6, [('a', (1, 1)), ('a', (2, 2))]);
+        let backward = AntennaMap::from_antennas(6, 6, [('a', (2, 2)), ('a', (1, 1))]);
+
+        assert_eq!(forward.antinodes(&rule)[&'a'], vec![(0, 0), (3, 3)]);
+        assert_eq!(backward.antinodes(&rule), forward.antinodes(&rule));
+    }
+
+    #[test]
+    fn test_large_sparse_map() {
+        let size = 1 << 40;
+        let map = AntennaMap::from_antennas(
+            size,
+            size,
+            [
+                ('x', (size / 2, size / 2)),
+                ('x', (size / 2 + 3, size / 2 + 1)),
+            ],
+        );
+
+        assert_eq!(
+            map.antinodes(&AntinodeRule::Harmonics(vec![-2, 3]))[&'x'],
+            vec![(size / 2 - 6, size / 2 - 2), (size / 2 + 9, size / 2 + 3)]
+        );
+    }
+
+    #[test]
+    fn test_visualize_adds_frequencies() {
+        let mut capture = Capture::default();
+        DayStruct.visualize(EXAMPLE, Part::One, &mut capture);
+
+        assert_eq!(capture.frames.len(), 2);
+        assert_eq!(
+            capture.frames[0].caption,
+            "frequency 0: 10 antinodes, 10 in total"
+        );
+        assert_eq!(
+            capture.frames[1].caption,
+            "frequency A: 5 antinodes, 14 in total"
+        );
+        assert!(capture.frames[1].highlighted.contains(&(5, 6)));
+        assert!(!capture.frames[1].highlighted.contains(&(1, 8)));
+    }
+
+    #[test]
+    fn test_svg_export_matches_golden() {
+        let mut capture = Capture::default();
+        DayStruct.visualize(EXAMPLE, Part::Two, &mut capture);
+        let svg = export::svg(capture.frames.last().unwrap(), 16, &DayStruct.colours());
+
+        assert_eq!(svg, include_str!("./example_part_two.svg"));
+    }
 }
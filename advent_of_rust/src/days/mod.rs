@@ -1,3 +1,38 @@
+use crate::utils::animation::FrameSink;
+use crate::utils::export::ColourMap;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Part {
+    #[clap(alias = "1")]
+    One,
+    #[clap(alias = "2")]
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExplainFormat {
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VisualFormat {
+    /// Print the final picture to the terminal
+    Text,
+    /// One binary PPM per frame
+    Ppm,
+    /// One PNG per frame
+    Png,
+    /// A single animated GIF
+    Gif,
+    /// One SVG per frame
+    Svg,
+}
+
 pub trait Day {
     type Output: std::fmt::Display;
 
@@ -5,6 +40,57 @@ pub trait Day {
     fn part_two(&self, input: &str) -> Self::Output;
 }
 
+/// Optional companion to [`Day`] for days that can show how they reached their
+/// answer, as a trace of records that are printed one per line.
+pub trait Explain: Day {
+    type Record: Serialize;
+
+    fn explain(&self, input: &str, part: Part) -> Vec<Self::Record>;
+
+    /// The record as a line of text.
+    fn describe(&self, record: &Self::Record, part: Part) -> String;
+
+    /// The answer printed after the records. Solves `input` again unless the
+    /// day can tell from its records.
+    fn answer(&self, _records: &[Self::Record], input: &str, part: Part) -> String {
+        match part {
+            Part::One => self.part_one(input).to_string(),
+            Part::Two => self.part_two(input).to_string(),
+        }
+    }
+}
+
+/// Optional companion to [`Day`] for grid puzzles that can show their
+/// progress as a sequence of frames.
+pub trait Visualize: Day {
+    fn visualize(&self, input: &str, part: Part, sink: &mut dyn FrameSink);
+
+    /// Colours for exporting the frames as images.
+    fn colours(&self) -> ColourMap {
+        ColourMap::default()
+    }
+}
+
+/// Prints a day's trace as text, followed by the answer, or as one JSON
+/// object per record.
+pub fn explain<E: Explain>(day: &E, input: &str, part: Part, format: ExplainFormat) -> String {
+    let mut out = String::new();
+    let records = day.explain(input, part);
+    for record in &records {
+        match format {
+            ExplainFormat::Text => writeln!(out, "{}", day.describe(record, part)),
+            ExplainFormat::Json => writeln!(out, "{}", serde_json::to_string(record).unwrap()),
+        }
+        .unwrap();
+    }
+
+    if format == ExplainFormat::Text {
+        writeln!(out, "answer: {}", day.answer(&records, input, part)).unwrap();
+    }
+
+    out
+}
+
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -14,4 +100,9 @@ pub mod day06;
 pub mod day07;
 pub mod day08;
 
-macros::generate_days_enum!();
+// Days implementing `Explain` or `Visualize` must be listed here to be
+// reachable through `Days`
+macros::generate_days_enum!(
+    explain: [day02, day03, day07],
+    visualize: [day04, day06, day08],
+);
@@ -0,0 +1,6 @@
+pub mod days;
+pub mod profile;
+pub mod runner;
+pub mod tui;
+pub mod utils;
+pub mod watch;
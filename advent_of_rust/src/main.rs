@@ -1,34 +1,653 @@
-use clap::{Parser, ValueEnum};
+use advent_of_rust::days::day08::{AntennaMap, AntinodeRule, RenderFormat};
+use advent_of_rust::days::{self, ExplainFormat, Part, VisualFormat};
+use advent_of_rust::profile::{self, CountingAllocator, Report};
+use advent_of_rust::runner::{self, Limits, Verdict};
+use advent_of_rust::tui;
+use advent_of_rust::utils::animation::{Frame, Palette, Terminal};
+use advent_of_rust::utils::columns::{Columns, Integer, Metric, MetricValue, Solver};
+use advent_of_rust::utils::export::{self, ColourMap, ColourRule, Exporter};
+use advent_of_rust::utils::grid::{Grid, Highlight};
+use advent_of_rust::utils::raster::GifWriter;
+use advent_of_rust::utils::word_search::{self, Direction, Overlap, Stencil};
+use advent_of_rust::watch;
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-mod days;
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[arg(short, long, required = true)]
+    day: Option<usize>,
+
+    #[arg(short, long, value_enum, required = true)]
+    part: Option<Part>,
+
+    /// Solve, explain or draw this file instead of the day's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Print how the answer was reached instead of just the answer
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "text")]
+    explain: Option<ExplainFormat>,
+
+    /// Draw the puzzle instead of printing the answer
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "text")]
+    visualize: Option<VisualFormat>,
+
+    /// File (gif) or directory (ppm, png, svg) to write the frames to
+    #[arg(long, default_value = "frames")]
+    output: PathBuf,
+
+    /// Number of steps between animation frames
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    frame_step: u64,
+
+    /// Width and height of a grid cell in pixels
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u64).range(1..))]
+    cell_size: u64,
+
+    /// Report allocations, bytes allocated and peak live memory for parsing and solving
+    #[arg(long)]
+    profile_memory: bool,
+
+    /// Colour override for exported frames, e.g. '#=e62828' or 'highlight=ffffff'
+    #[arg(long)]
+    colour: Vec<ColourRule>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Search a character grid for words or 2D patterns (day 4's input by default)
+    WordSearch(WordSearchArgs),
+    /// Compare two numeric columns with various metrics (day 1's input by default)
+    Columns(ColumnsArgs),
+    /// Report which page ordering rules each update breaks (day 5's input by default)
+    PageRules(PageRulesArgs),
+    /// List or draw the antinodes of every antenna frequency (day 8's input by default)
+    Antinodes(AntinodesArgs),
+    /// Play a day's solution frame by frame in the terminal
+    Visualize(VisualizeArgs),
+    /// Browse, run and check every day in an interactive dashboard
+    Tui(LimitArgs),
+    /// Re-run a day's tests and input whenever its module, example or input changes
+    Watch(WatchArgs),
+    /// Run days in their own processes, reporting panics, timeouts and OOMs per part
+    Run(RunArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+struct RunArgs {
+    /// Days to run, every implemented day by default
+    #[arg(short, long, value_delimiter = ',')]
+    days: Vec<usize>,
+
+    /// Only run this part
+    #[arg(short, long, value_enum)]
+    part: Option<Part>,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+
+/// Limits for parts run in their own processes.
+#[derive(ClapArgs, Debug)]
+struct LimitArgs {
+    /// Seconds a part may run for before it is killed
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: u64,
+
+    /// Memory (address space) limit for each part in MiB
+    #[arg(long)]
+    memory_limit: Option<u64>,
+}
+
+impl LimitArgs {
+    fn limits(&self) -> Limits {
+        Limits {
+            timeout: Some(Duration::from_secs(self.timeout)),
+            memory: self.memory_limit.map(|mib| mib << 20),
+        }
+    }
+}
+
+#[derive(ClapArgs, Debug)]
+struct WatchArgs {
     #[arg(short, long)]
     day: usize,
 
-    #[arg(short, long, value_enum)]
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct VisualizeArgs {
+    #[arg(short, long)]
+    day: usize,
+
+    #[arg(short, long, value_enum, default_value_t = Part::One)]
+    part: Part,
+
+    /// Colours to draw the grid in
+    #[arg(long, value_enum, default_value_t = Palette::Dark)]
+    palette: Palette,
+
+    /// Frames per second to play at, changed with + and - while playing
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    fps: u32,
+
+    /// Only show every this many frames
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    every: u64,
+
+    /// Draw this file instead of the day's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct AntinodesArgs {
+    /// Antenna map to read instead of day 8's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Which puzzle part's antinode rule to apply
+    #[arg(short, long, value_enum, default_value_t = Part::One)]
     part: Part,
+
+    /// Antinodes where one antenna is this many times as far as the other,
+    /// instead of the part's rule
+    #[arg(long, conflicts_with = "harmonics")]
+    ratio: Option<u32>,
+
+    /// Antinodes at a + k * (b - a) for each k, instead of the part's rule
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+    harmonics: Option<Vec<i64>>,
+
+    /// Draw the map with the antinodes overlaid instead of listing them
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "ansi")]
+    render: Option<RenderFormat>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct PageRulesArgs {
+    /// Rules and updates to read instead of day 5's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Print the rules as a Graphviz DOT graph instead of the report
+    #[arg(long)]
+    dot: bool,
+
+    /// Only graph the rules between the pages of this update (1-based)
+    #[arg(long, requires = "dot")]
+    update: Option<usize>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct ColumnsArgs {
+    /// Whitespace separated columns to read instead of day 1's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Metrics to compute
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Metric::Distance, Metric::Similarity])]
+    metrics: Vec<Metric>,
+
+    /// Index of the left column
+    #[arg(long, default_value_t = 0)]
+    left: usize,
+
+    /// Index of the right column
+    #[arg(long, default_value_t = 1)]
+    right: usize,
+
+    /// How to compute the distance and similarity metrics
+    #[arg(long, value_enum, default_value_t = Solver::Comparison)]
+    solver: Solver,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
-enum Part {
-    #[clap(alias = "1")]
-    One,
-    #[clap(alias = "2")]
-    Two,
+#[derive(ClapArgs, Debug)]
+struct WordSearchArgs {
+    /// Grid to search instead of day 4's input
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Word to search for
+    #[arg(long, default_value = days::day04::XMAS, conflicts_with = "stencil")]
+    word: String,
+
+    /// Directions to read the word in
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Direction::ALL)]
+    directions: Vec<Direction>,
+
+    /// Only count matches along the same line that don't share cells
+    #[arg(long)]
+    no_overlap: bool,
+
+    /// 2D pattern with rows separated by '/' and '.' as a wildcard, e.g. M.S/.A./M.S
+    #[arg(long)]
+    stencil: Option<String>,
+
+    /// Also match the stencil rotated by 90, 180 and 270 degrees
+    #[arg(long, requires = "stencil")]
+    rotate: bool,
+
+    /// Reprint the grid with matched cells highlighted, along with a breakdown
+    /// per direction (words) or the center of every match (stencils)
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "ansi")]
+    render: Option<Highlight>,
 }
 
 fn main() {
     let args = Args::parse();
 
-    if let Some(day) = days::Days::from_day_number(args.day) {
-        match args.part {
-            Part::One => println!("{}", day.part_one()),
-            Part::Two => println!("{}", day.part_two()),
+    if let Some(command) = args.command {
+        match command {
+            Command::WordSearch(args) => word_search(args),
+            Command::Columns(args) => compare_columns(args),
+            Command::PageRules(args) => page_rules(args),
+            Command::Antinodes(args) => antinodes(args),
+            Command::Visualize(args) => visualize(args),
+            Command::Tui(args) => {
+                if let Err(err) = tui::run(args.limits()) {
+                    eprintln!("Failed to run the dashboard: {}", err);
+                }
+            }
+            Command::Watch(args) => watch(args),
+            Command::Run(args) => run_days(args),
+        }
+        return;
+    }
+
+    let (day_number, part) = (args.day.unwrap(), args.part.unwrap());
+    if let Some(day) = days::Days::from_day_number(day_number) {
+        let input = match &args.input {
+            Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+            None => day.input(),
         };
+
+        if let Some(format) = args.explain {
+            match day.explain(&input, part, format) {
+                Some(explanation) => print!("{}", explanation),
+                None => eprintln!("Day {} does not support --explain", day_number),
+            }
+            return;
+        }
+
+        if let Some(format) = args.visualize {
+            return export_visualization(&day, &input, part, format, &args);
+        }
+
+        if args.profile_memory {
+            let (answer, report) = profile::measure(|| day.solve(part, &input));
+            println!("{}", answer);
+            return print_memory_report(&report);
+        }
+
+        println!("{}", day.solve(part, &input));
     } else {
-        eprintln!("Day {} not implemented", args.day);
+        eprintln!("Day {} not implemented", day_number);
+    }
+}
+
+fn print_memory_report(report: &Report) {
+    println!(
+        "{:<7}{:>12}{:>16}{:>16}",
+        "", "allocations", "bytes", "peak bytes"
+    );
+    for (phase, usage) in [("parse", report.parse), ("solve", report.solve)] {
+        println!(
+            "{:<7}{:>12}{:>16}{:>16}",
+            phase, usage.allocations, usage.bytes, usage.peak
+        );
+    }
+}
+
+fn export_visualization(
+    day: &days::Days,
+    input: &str,
+    part: Part,
+    format: VisualFormat,
+    args: &Args,
+) {
+    let mut colours = day.colours();
+    for rule in &args.colour {
+        colours.apply(rule);
+    }
+    let (step, cell_size) = (args.frame_step as usize, args.cell_size as usize);
+
+    let exported = match format {
+        VisualFormat::Text => {
+            let mut last = None;
+            let exported = export_with(day, input, part, usize::MAX, |frame| {
+                last = Some(frame);
+                Ok(())
+            });
+            if let Some(frame) = last {
+                print!("{}", frame.grid.render(&frame.highlighted, Highlight::Ansi));
+                println!("{}", frame.caption);
+                return;
+            }
+            exported
+        }
+        VisualFormat::Gif => {
+            let mut gif = None;
+            export_with(day, input, part, step, |frame| {
+                let image = export::image(&frame, cell_size, &colours);
+                let gif = match &mut gif {
+                    Some(gif) => gif,
+                    None => gif.insert(GifWriter::new(
+                        BufWriter::new(File::create(&args.output)?),
+                        image.width(),
+                        image.height(),
+                        &colours.palette(),
+                        4,
+                    )?),
+                };
+                gif.write(&image)
+            })
+        }
+        _ => {
+            let mut index = 0;
+            export_with(day, input, part, step, |frame| {
+                if index == 0 {
+                    std::fs::create_dir_all(&args.output)?;
+                }
+                write_frame(&frame, index, format, cell_size, &colours, &args.output)?;
+                index += 1;
+                Ok(())
+            })
+        }
+    };
+
+    match exported {
+        None => eprintln!("Day {} has no visualizer", args.day.unwrap()),
+        Some(Ok(0)) => eprintln!("Nothing to draw"),
+        Some(Ok(frames)) => println!("Wrote {} frames to {}", frames, args.output.display()),
+        Some(Err(err)) => eprintln!("Failed to write {}: {}", args.output.display(), err),
+    }
+}
+
+/// Visualizes `day` on `input`, passing every `step`th frame to `write` as it is drawn.
+/// Returns `None` if the day has no visualizer.
+fn export_with(
+    day: &days::Days,
+    input: &str,
+    part: Part,
+    step: usize,
+    write: impl FnMut(Frame) -> std::io::Result<()>,
+) -> Option<std::io::Result<usize>> {
+    let mut exporter = Exporter::new(step, write);
+
+    day.visualize(input, part, &mut exporter)
+        .then(|| exporter.result())
+}
+
+fn write_frame(
+    frame: &Frame,
+    index: usize,
+    format: VisualFormat,
+    cell_size: usize,
+    colours: &ColourMap,
+    output: &Path,
+) -> std::io::Result<()> {
+    let extension = match format {
+        VisualFormat::Png => "png",
+        VisualFormat::Svg => "svg",
+        _ => "ppm",
+    };
+    let mut file = BufWriter::new(File::create(
+        output.join(format!("frame_{:05}.{}", index, extension)),
+    )?);
+
+    match format {
+        VisualFormat::Png => export::image(frame, cell_size, colours).write_png(file),
+        VisualFormat::Svg => file.write_all(export::svg(frame, cell_size, colours).as_bytes()),
+        _ => export::image(frame, cell_size, colours).write_ppm(file),
+    }
+}
+
+fn word_search(args: WordSearchArgs) {
+    let input = match args.input {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+        None => days::Days::from_day_number(4).unwrap().input(),
+    };
+    let grid = match Grid::parse(&input) {
+        Ok(grid) => grid,
+        Err(err) => return eprintln!("Invalid grid: {}", err),
+    };
+
+    let mut highlighted: HashSet<(usize, usize)> = HashSet::new();
+    let mut breakdown: Vec<String> = vec![];
+
+    let count = match args.stencil {
+        Some(pattern) => {
+            let stencil = match Stencil::parse(&pattern) {
+                Ok(stencil) => stencil,
+                Err(err) => return eprintln!("Invalid stencil: {}", err),
+            };
+            let stencils = if args.rotate {
+                stencil.rotations()
+            } else {
+                vec![stencil]
+            };
+
+            let mut centers = vec![];
+            for stencil in &stencils {
+                for origin in stencil.find(&grid) {
+                    highlighted.extend(stencil.cells_at(origin).map(|(pos, _)| pos));
+                    centers.push(stencil.center(origin));
+                }
+            }
+
+            centers.sort_unstable();
+            breakdown.extend(
+                centers
+                    .iter()
+                    .map(|(row, col)| format!("center: row {}, col {}", row, col)),
+            );
+            centers.len()
+        }
+        None => {
+            let overlap = if args.no_overlap {
+                Overlap::Disallowed
+            } else {
+                Overlap::Allowed
+            };
+
+            let matches = word_search::find_word(&grid, &args.word, &args.directions, overlap);
+            highlighted.extend(matches.iter().flat_map(|m| m.cells()));
+            breakdown.extend(args.directions.iter().map(|&direction| {
+                let count = matches.iter().filter(|m| m.direction == direction).count();
+                format!("{:<3} {}", direction, count)
+            }));
+            matches.len()
+        }
+    };
+
+    if let Some(highlight) = args.render {
+        print!("{}", grid.render(&highlighted, highlight));
+        println!();
+        for line in &breakdown {
+            println!("{}", line);
+        }
+        println!("total: {}", count);
+    } else {
+        println!("{}", count);
+    }
+}
+
+fn compare_columns(args: ColumnsArgs) {
+    let input = match &args.input {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+        None => days::Days::from_day_number(1).unwrap().input(),
+    };
+    match args.solver {
+        Solver::Comparison => print_metrics::<i128>(&input, &args, Metric::compute),
+        Solver::Counting => print_metrics::<u32>(&input, &args, Metric::compute_counting),
+    }
+}
+
+fn print_metrics<T: Integer>(
+    input: &str,
+    args: &ColumnsArgs,
+    compute: fn(Metric, &[T], &[T]) -> MetricValue,
+) {
+    let columns = match Columns::<T>::parse(input) {
+        Ok(columns) => columns,
+        Err(err) => return eprintln!("Invalid columns: {}", err),
+    };
+    let (Some(left), Some(right)) = (columns.column(args.left), columns.column(args.right)) else {
+        return eprintln!("Input only has {} columns", columns.count());
+    };
+
+    for &metric in &args.metrics {
+        let name = metric.to_possible_value().unwrap();
+        println!("{}: {}", name.get_name(), compute(metric, left, right));
+    }
+}
+
+fn page_rules(args: PageRulesArgs) {
+    let input = match args.input {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+        None => days::Days::from_day_number(5).unwrap().input(),
+    };
+
+    if !args.dot {
+        return print!("{}", days::day05::report(&input));
+    }
+
+    match days::day05::to_dot(&input, args.update) {
+        Ok(dot) => print!("{}", dot),
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+fn visualize(args: VisualizeArgs) {
+    let Some(day) = days::Days::from_day_number(args.day) else {
+        return eprintln!("Day {} not implemented", args.day);
+    };
+    let input = match &args.input {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+        None => day.input(),
+    };
+    let mut terminal =
+        match Terminal::new(args.palette, day.colours(), args.fps, args.every as usize) {
+            Ok(terminal) => terminal,
+            Err(err) => return eprintln!("Failed to set up the terminal: {}", err),
+        };
+
+    if !day.visualize(&input, args.part, &mut terminal) {
+        drop(terminal);
+        eprintln!("Day {} does not support visualize", args.day);
+    }
+}
+
+fn run_days(args: RunArgs) {
+    let mut failures = 0;
+    let days: Vec<days::Days> = if args.days.is_empty() {
+        days::Days::ALL.to_vec()
+    } else {
+        args.days
+            .iter()
+            .filter_map(|&number| {
+                let day = days::Days::from_day_number(number);
+                if day.is_none() {
+                    eprintln!("Day {} not implemented", number);
+                    failures += 1;
+                }
+                day
+            })
+            .collect()
+    };
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => vec![Part::One, Part::Two],
+    };
+    let limits = args.limits.limits();
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => return eprintln!("Failed to find the executable: {}", err),
+    };
+
+    for day in days {
+        for &part in &parts {
+            let process = runner::solver(&exe, day, part, None);
+            let result = match runner::run_isolated(process, &limits) {
+                Ok((verdict, elapsed)) => {
+                    if !matches!(verdict, Verdict::Answer(_)) {
+                        failures += 1;
+                    }
+                    format!("{} ({:.2?})", verdict, elapsed)
+                }
+                Err(err) => {
+                    failures += 1;
+                    format!("failed to start: {}", err)
+                }
+            };
+            let part_name = part.to_possible_value().unwrap();
+            println!(
+                "day {} part {}: {}",
+                day.day_number(),
+                part_name.get_name(),
+                result
+            );
+        }
+    }
+
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn watch(args: WatchArgs) {
+    let Some(day) = days::Days::from_day_number(args.day) else {
+        return eprintln!("Day {} not implemented", args.day);
+    };
+
+    if let Err(err) = watch::watch(day, &args.limits.limits()) {
+        eprintln!("Failed to watch {}: {}", day.dir(), err);
+    }
+}
+
+fn antinodes(args: AntinodesArgs) {
+    let input = match args.input {
+        Some(path) => std::fs::read_to_string(path).expect("Failed to load input file"),
+        None => days::Days::from_day_number(8).unwrap().input(),
+    };
+    let map = match AntennaMap::parse(&input) {
+        Ok(map) => map,
+        Err(err) => return eprintln!("Invalid antenna map: {}", err),
+    };
+    let rule = match (args.ratio, args.harmonics) {
+        (Some(ratio), _) => AntinodeRule::Ratio(ratio),
+        (_, Some(multiples)) => AntinodeRule::Harmonics(multiples),
+        _ => AntinodeRule::for_part(args.part),
+    };
+    let antinodes = map.antinodes(&rule);
+
+    if let Some(format) = args.render {
+        return print!("{}", map.render(&antinodes, format));
+    }
+
+    let mut all: HashSet<(usize, usize)> = HashSet::new();
+    for (frequency, cells) in &antinodes {
+        let cells_text: Vec<String> = cells
+            .iter()
+            .map(|(row, col)| format!("({}, {})", row, col))
+            .collect();
+        println!("{}: {}", frequency, cells_text.join(" "));
+        all.extend(cells);
     }
+    println!("total: {}", all.len());
 }
@@ -0,0 +1,221 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering::Relaxed};
+
+/// What a thread is doing when it allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Parse,
+    Solve,
+}
+
+thread_local! {
+    static PHASE: Cell<Phase> = const { Cell::new(Phase::Solve) };
+}
+
+/// Marks the allocations made by `f` on this thread as parsing rather than
+/// solving. Days wrap the code that turns their input into data with this.
+pub fn parsing<T>(f: impl FnOnce() -> T) -> T {
+    let previous = PHASE.with(|phase| phase.replace(Phase::Parse));
+    let out = f();
+    PHASE.with(|phase| phase.set(previous));
+
+    out
+}
+
+/// Memory used by one phase of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Usage {
+    pub allocations: u64,
+    pub bytes: u64,
+    /// Most bytes live at once while in this phase, including anything still
+    /// held from the other phase
+    pub peak: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Report {
+    pub parse: Usage,
+    pub solve: Usage,
+}
+
+struct PhaseCounters {
+    allocations: AtomicU64,
+    bytes: AtomicU64,
+    peak: AtomicI64,
+}
+
+impl PhaseCounters {
+    const fn new() -> Self {
+        PhaseCounters {
+            allocations: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            peak: AtomicI64::new(0),
+        }
+    }
+}
+
+/// Running totals. Live bytes are counted from the last reset, so freeing
+/// memory allocated before it can take them below zero.
+struct Counters {
+    live: AtomicI64,
+    parse: PhaseCounters,
+    solve: PhaseCounters,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Counters {
+            live: AtomicI64::new(0),
+            parse: PhaseCounters::new(),
+            solve: PhaseCounters::new(),
+        }
+    }
+
+    fn reset(&self) {
+        self.live.store(0, Relaxed);
+        for counters in [&self.parse, &self.solve] {
+            counters.allocations.store(0, Relaxed);
+            counters.bytes.store(0, Relaxed);
+            counters.peak.store(0, Relaxed);
+        }
+    }
+
+    /// An allocation of `size` bytes that grew live memory by `growth`.
+    fn allocated(&self, phase: Phase, size: usize, growth: i64) {
+        let counters = match phase {
+            Phase::Parse => &self.parse,
+            Phase::Solve => &self.solve,
+        };
+
+        counters.allocations.fetch_add(1, Relaxed);
+        counters.bytes.fetch_add(size as u64, Relaxed);
+        let live = self.live.fetch_add(growth, Relaxed) + growth;
+        counters.peak.fetch_max(live, Relaxed);
+    }
+
+    fn freed(&self, size: usize) {
+        self.live.fetch_sub(size as i64, Relaxed);
+    }
+
+    fn report(&self) -> Report {
+        let usage = |counters: &PhaseCounters| Usage {
+            allocations: counters.allocations.load(Relaxed),
+            bytes: counters.bytes.load(Relaxed),
+            peak: counters.peak.load(Relaxed).max(0) as u64,
+        };
+
+        Report {
+            parse: usage(&self.parse),
+            solve: usage(&self.solve),
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COUNTERS: Counters = Counters::new();
+
+/// The system allocator, counting allocations while [`measure`] runs. The
+/// binary installs it as its global allocator.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn allocated(size: usize, growth: i64) {
+        if ENABLED.load(Relaxed) {
+            // Threads being torn down have no phase left, count them as solving
+            let phase = PHASE.try_with(Cell::get).unwrap_or(Phase::Solve);
+            COUNTERS.allocated(phase, size, growth);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::allocated(layout.size(), layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Self::allocated(layout.size(), layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        if ENABLED.load(Relaxed) {
+            COUNTERS.freed(layout.size());
+        }
+    }
+
+    /// Counted as an allocation of the new size.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            Self::allocated(new_size, new_size as i64 - layout.size() as i64);
+        }
+        new_ptr
+    }
+}
+
+/// Runs `f`, counting the allocations made on any thread in the meantime.
+/// Only meaningful with [`CountingAllocator`] installed and one measurement
+/// running at a time.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Report) {
+    COUNTERS.reset();
+    ENABLED.store(true, Relaxed);
+    let out = f();
+    ENABLED.store(false, Relaxed);
+
+    (out, COUNTERS.report())
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let counters = Counters::new();
+        counters.allocated(Phase::Parse, 100, 100);
+        counters.allocated(Phase::Parse, 50, 50);
+        counters.freed(100);
+        counters.allocated(Phase::Solve, 400, 400);
+        // A realloc from 400 to 500 bytes
+        counters.allocated(Phase::Solve, 500, 100);
+        counters.freed(500);
+
+        assert_eq!(
+            counters.report(),
+            Report {
+                parse: Usage {
+                    allocations: 2,
+                    bytes: 150,
+                    peak: 150
+                },
+                solve: Usage {
+                    allocations: 2,
+                    bytes: 900,
+                    peak: 550
+                },
+            }
+        );
+
+        counters.reset();
+        assert_eq!(counters.report(), Report::default());
+    }
+
+    #[test]
+    fn test_parsing_restores_phase() {
+        let phase = || PHASE.with(Cell::get);
+
+        let inner = parsing(|| parsing(phase));
+        assert_eq!(inner, Phase::Parse);
+        assert_eq!(phase(), Phase::Solve);
+    }
+}
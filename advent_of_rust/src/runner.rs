@@ -0,0 +1,205 @@
+use crate::days::{Days, Part};
+use clap::ValueEnum;
+use std::fmt;
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits for a part run in its own process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// Address space limit in bytes
+    pub memory: Option<u64>,
+}
+
+/// How a part run in its own process ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Answer(String),
+    Panicked(String),
+    TimedOut,
+    OutOfMemory,
+    /// Exited unsuccessfully without panicking, e.g. killed by a signal
+    Failed(String),
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Answer(answer) => write!(f, "{}", answer),
+            Verdict::Panicked(message) => write!(f, "panicked: {}", message),
+            Verdict::TimedOut => write!(f, "timed out"),
+            Verdict::OutOfMemory => write!(f, "OOM"),
+            Verdict::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+/// The binary at `exe` solving `part` of `day`, on `input` rather than the
+/// day's own input if given.
+pub fn solver(exe: &Path, day: Days, part: Part, input: Option<&Path>) -> Command {
+    let part = part.to_possible_value().unwrap();
+    let mut command = Command::new(exe);
+    command.args([
+        "--day",
+        &day.day_number().to_string(),
+        "--part",
+        part.get_name(),
+    ]);
+    if let Some(input) = input {
+        command.arg("--input").arg(input);
+    }
+
+    command
+}
+
+/// Runs `command`, which should print a part's answer, killing it if it
+/// outlives the timeout. Also returns how long it ran for.
+pub fn run_isolated(mut command: Command, limits: &Limits) -> io::Result<(Verdict, Duration)> {
+    if let Some(bytes) = limits.memory {
+        let limit = libc::rlimit {
+            rlim_cur: bytes,
+            rlim_max: bytes,
+        };
+        // SAFETY: setrlimit is async-signal-safe and only touches the child
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    let start = Instant::now();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read the pipes on their own threads so a chatty child can't block on a
+    // full pipe while we wait for it
+    let read = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut out = String::new();
+            let _ = pipe.read_to_string(&mut out);
+            out
+        })
+    };
+    let stdout = read(Box::new(child.stdout.take().unwrap()));
+    let stderr = read(Box::new(child.stderr.take().unwrap()));
+
+    // Poll quickly at first so that fast parts are timed accurately
+    let mut delay = Duration::from_micros(50);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if limits
+            .timeout
+            .is_some_and(|timeout| start.elapsed() >= timeout)
+        {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(delay);
+        delay = (delay * 2).min(Duration::from_millis(10));
+    };
+    let elapsed = start.elapsed();
+
+    let (stdout, stderr) = (stdout.join().unwrap(), stderr.join().unwrap());
+    let verdict = match status {
+        None => Verdict::TimedOut,
+        Some(status) => classify(status, &stdout, &stderr),
+    };
+
+    Ok((verdict, elapsed))
+}
+
+/// Works out how a run ended from its exit status and output.
+pub fn classify(status: ExitStatus, stdout: &str, stderr: &str) -> Verdict {
+    if status.success() {
+        return Verdict::Answer(stdout.trim().to_string());
+    }
+    if stderr.contains("memory allocation of") {
+        return Verdict::OutOfMemory;
+    }
+
+    let mut lines = stderr.lines().map(str::trim);
+    if lines.any(|l| l.starts_with("thread '") && l.contains("panicked at")) {
+        let message: Vec<&str> = lines.take_while(|l| !l.starts_with("note:")).collect();
+        return Verdict::Panicked(message.join(" "));
+    }
+
+    // Skip cargo's own complaint about the exit code when run through it
+    let reason = stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty() && !l.starts_with("error: process didn't exit"));
+    Verdict::Failed(reason.map_or_else(|| status.to_string(), str::to_string))
+}
+
+#[cfg(test)]
+mod runner_tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[test]
+    fn test_classify() {
+        let panic = "\
+thread 'main' panicked at src/days/day06/mod.rs:10:5:
+index out of bounds
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+";
+
+        let (ok, failed) = (ExitStatus::from_raw(0), ExitStatus::from_raw(101 << 8));
+
+        assert_eq!(classify(ok, "41\n", ""), Verdict::Answer("41".to_string()));
+        assert_eq!(
+            classify(failed, "", panic),
+            Verdict::Panicked("index out of bounds".to_string())
+        );
+        assert_eq!(
+            classify(failed, "", "memory allocation of 1073741824 bytes failed\n"),
+            Verdict::OutOfMemory
+        );
+        assert_eq!(
+            classify(failed, "", "Day 9 not implemented\n"),
+            Verdict::Failed("Day 9 not implemented".to_string())
+        );
+        assert_eq!(
+            classify(ExitStatus::from_raw(9), "", ""),
+            Verdict::Failed("signal: 9 (SIGKILL)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_isolated() {
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(200)),
+            memory: Some(64 << 20),
+        };
+
+        let (verdict, _) = run_isolated(sh("echo 41"), &limits).unwrap();
+        assert_eq!(verdict, Verdict::Answer("41".to_string()));
+
+        let (verdict, elapsed) = run_isolated(sh("exec sleep 5"), &limits).unwrap();
+        assert_eq!(verdict, Verdict::TimedOut);
+        assert!(elapsed < Duration::from_secs(5));
+
+        let (verdict, _) = run_isolated(sh("ulimit -v"), &limits).unwrap();
+        assert_eq!(verdict, Verdict::Answer((64 << 10).to_string()));
+    }
+}
@@ -0,0 +1,592 @@
+use crate::days::{Days, Part};
+use crate::runner::{self, Limits, Verdict};
+use clap::ValueEnum;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Input,
+    Example,
+}
+
+impl Source {
+    fn file_name(self) -> &'static str {
+        match self {
+            Source::Input => "input.txt",
+            Source::Example => "example.txt",
+        }
+    }
+}
+
+/// A part of a day to run on its input or example.
+type Job = (Days, Part, Source);
+
+/// Makes the command that solves a part on the given file.
+type Solver = dyn Fn(Days, Part, &Path) -> Command + Send;
+
+/// The outcome of running one part of a day in its own process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub verdict: Verdict,
+    pub elapsed: Duration,
+}
+
+/// How an answer on the real input compares with the day's `answers.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Correct,
+    Wrong { expected: String },
+    Unrecorded,
+}
+
+/// A dashboard listing every day, with the selected day's input alongside.
+/// Parts run one at a time on a worker thread, each in its own process, so
+/// a part that hangs or crashes can't take the dashboard with it.
+pub struct App {
+    selected: usize,
+    part: Part,
+    source: Source,
+    runs: HashMap<Job, Run>,
+    /// Jobs sent to the worker that haven't finished yet
+    pending: HashSet<Job>,
+    jobs: Sender<Job>,
+    results: Receiver<(Job, Run)>,
+    recorded: HashMap<(Days, Part), String>,
+    /// Where each day's recorded answers are kept
+    answers: Box<dyn Fn(Days) -> PathBuf>,
+    /// The selected day's input or example, read when the selection changes
+    contents: String,
+    message: Option<String>,
+    scroll: u16,
+    quit: bool,
+}
+
+impl App {
+    /// A dashboard running parts with this executable and keeping answers in
+    /// each day's own `answers.txt`.
+    pub fn new(limits: Limits) -> io::Result<App> {
+        let exe = std::env::current_exe()?;
+
+        Ok(App::with(
+            move |day, part, input| runner::solver(&exe, day, part, Some(input)),
+            limits,
+            |day| PathBuf::from(day.dir()).join("answers.txt"),
+        ))
+    }
+
+    /// A dashboard running the commands `solver` makes within `limits`, and
+    /// reading and recording answers in the file `answers` gives for each day.
+    pub fn with(
+        solver: impl Fn(Days, Part, &Path) -> Command + Send + 'static,
+        limits: Limits,
+        answers: impl Fn(Days) -> PathBuf + 'static,
+    ) -> App {
+        let (jobs, results) = worker(Box::new(solver), limits);
+        let recorded = Days::ALL
+            .iter()
+            .flat_map(|&day| read_answers(day, &answers(day)).into_iter())
+            .collect();
+
+        let mut app = App {
+            selected: 0,
+            part: Part::One,
+            source: Source::Input,
+            runs: HashMap::new(),
+            pending: HashSet::new(),
+            jobs,
+            results,
+            recorded,
+            answers: Box::new(answers),
+            contents: String::new(),
+            message: None,
+            scroll: 0,
+            quit: false,
+        };
+        app.load_contents();
+
+        app
+    }
+
+    /// Draws and handles keys until `q` is pressed.
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            while let Ok((job, run)) = self.results.try_recv() {
+                self.finished(job, run);
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Wake up now and then to show runs finishing
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Blocks until every part sent to the worker has finished.
+    pub fn wait(&mut self) {
+        while !self.pending.is_empty() {
+            let Ok((job, run)) = self.results.recv() else {
+                return;
+            };
+            self.finished(job, run);
+        }
+    }
+
+    fn finished(&mut self, job: Job, run: Run) {
+        self.pending.remove(&job);
+        self.runs.insert(job, run);
+    }
+
+    fn day(&self) -> Days {
+        Days::ALL[self.selected]
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(self.day().dir()).join(self.source.file_name())
+    }
+
+    /// Reads the selected day's input or example, scrolled back to the top.
+    fn load_contents(&mut self) {
+        let path = self.path();
+        self.contents = fs::read_to_string(&path)
+            .unwrap_or_else(|err| format!("Failed to read {}: {}", path.display(), err));
+        self.scroll = 0;
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) {
+        self.message = None;
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                self.load_contents();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(Days::ALL.len() - 1);
+                self.load_contents();
+            }
+            KeyCode::Char('1') => self.part = Part::One,
+            KeyCode::Char('2') => self.part = Part::Two,
+            KeyCode::Tab | KeyCode::Char('e') => {
+                self.source = match self.source {
+                    Source::Input => Source::Example,
+                    Source::Example => Source::Input,
+                };
+                self.load_contents();
+            }
+            KeyCode::Enter => self.run_part(self.day(), self.part),
+            KeyCode::Char('a') => {
+                for &day in Days::ALL {
+                    self.run_part(day, Part::One);
+                    self.run_part(day, Part::Two);
+                }
+            }
+            KeyCode::Char('r') => self.record(),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            _ => {}
+        }
+    }
+
+    fn run_part(&mut self, day: Days, part: Part) {
+        let job = (day, part, self.source);
+        if self.pending.insert(job) {
+            self.jobs
+                .send(job)
+                .expect("the worker outlives the dashboard");
+        }
+    }
+
+    /// Saves the selected part's answer on the real input to `answers.txt`.
+    fn record(&mut self) {
+        let (day, part) = (self.day(), self.part);
+        let Some(Run {
+            verdict: Verdict::Answer(answer),
+            ..
+        }) = self.runs.get(&(day, part, Source::Input))
+        else {
+            self.message = Some("Run the part on the input before recording it".to_string());
+            return;
+        };
+
+        self.recorded.insert((day, part), answer.clone());
+        self.message = Some(
+            match write_answers(day, &self.recorded, &(self.answers)(day)) {
+                Ok(()) => format!("Recorded {} for day {}", answer, day.day_number()),
+                Err(err) => format!("Failed to record the answer: {}", err),
+            },
+        );
+    }
+
+    /// `None` unless the part has been run on the real input and answered.
+    pub fn status(&self, day: Days, part: Part) -> Option<Status> {
+        let Verdict::Answer(answer) = &self.runs.get(&(day, part, Source::Input))?.verdict else {
+            return None;
+        };
+
+        Some(match self.recorded.get(&(day, part)) {
+            Some(expected) if expected == answer => Status::Correct,
+            Some(expected) => Status::Wrong {
+                expected: expected.clone(),
+            },
+            None => Status::Unrecorded,
+        })
+    }
+
+    fn cell(&self, day: Days, part: Part) -> String {
+        let job = (day, part, self.source);
+        if self.pending.contains(&job) {
+            return "running...".to_string();
+        }
+        let Some(run) = self.runs.get(&job) else {
+            return "-".to_string();
+        };
+        let answer = match &run.verdict {
+            Verdict::Answer(answer) => answer.as_str(),
+            Verdict::Panicked(_) => "panicked",
+            Verdict::TimedOut => "timed out",
+            Verdict::OutOfMemory => "OOM",
+            Verdict::Failed(_) => "failed",
+        };
+        let mark = match self.source {
+            Source::Input => match self.status(day, part) {
+                Some(Status::Correct) => " ✓",
+                Some(Status::Wrong { .. }) => " ✗",
+                _ => "",
+            },
+            Source::Example => "",
+        };
+
+        format!("{}{} {:.2?}", answer, mark, run.elapsed)
+    }
+
+    /// The selected part's answer or error in full, or the last message.
+    fn details(&self) -> String {
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+
+        let (day, part) = (self.day(), self.part);
+        let Some(run) = self.runs.get(&(day, part, self.source)) else {
+            return format!("Day {} part {:?} has not been run", day.day_number(), part);
+        };
+
+        let status = match self.source {
+            Source::Input => self.status(day, part),
+            Source::Example => None,
+        };
+        match (&run.verdict, status) {
+            (Verdict::Answer(answer), Some(Status::Wrong { expected })) => {
+                format!("{} (recorded answer is {})", answer, expected)
+            }
+            (Verdict::Answer(answer), Some(Status::Unrecorded)) => {
+                format!("{} (not recorded)", answer)
+            }
+            (verdict, _) => verdict.to_string(),
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(frame.area());
+        let [days, footer] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(4)]).areas(left);
+
+        let part_header = |part: Part, title: &'static str| {
+            let style = Style::new().add_modifier(Modifier::BOLD);
+            if part == self.part {
+                Line::styled(title, style.add_modifier(Modifier::UNDERLINED))
+            } else {
+                Line::styled(title, style)
+            }
+        };
+        let rows = Days::ALL.iter().map(|&day| {
+            Row::new([
+                format!("Day {}", day.day_number()),
+                self.cell(day, Part::One),
+                self.cell(day, Part::Two),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new([
+            Line::styled("", Style::new()),
+            part_header(Part::One, "Part 1"),
+            part_header(Part::Two, "Part 2"),
+        ]))
+        .row_highlight_style(Style::new().bg(Color::DarkGray))
+        .block(Block::bordered().title(format!(" Days ({}) ", self.source.file_name())));
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, days, &mut state);
+
+        let help = "↑↓ day  1/2 part  tab source  enter run  a all  r record  q quit";
+        frame.render_widget(
+            Paragraph::new(vec![Line::raw(self.details()), Line::raw(help)])
+                .block(Block::bordered()),
+            footer,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.contents.as_str())
+                .scroll((self.scroll, 0))
+                .block(Block::bordered().title(format!(
+                    " day{:02}/{} ",
+                    self.day().day_number(),
+                    self.source.file_name()
+                ))),
+            right,
+        );
+    }
+}
+
+/// Takes over the terminal and runs the dashboard until it is quit.
+pub fn run(limits: Limits) -> io::Result<()> {
+    let app = App::new(limits)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    result
+}
+
+/// Starts a thread running the jobs it is sent one after another, so their
+/// timings don't suffer from running side by side.
+fn worker(solver: Box<Solver>, limits: Limits) -> (Sender<Job>, Receiver<(Job, Run)>) {
+    let (jobs, job_rx) = mpsc::channel::<Job>();
+    let (result_tx, results) = mpsc::channel();
+
+    thread::spawn(move || {
+        for job in job_rx {
+            let run = run_part(&solver, job, &limits);
+            if result_tx.send((job, run)).is_err() {
+                break;
+            }
+        }
+    });
+
+    (jobs, results)
+}
+
+/// Runs one part on the day's input or example in its own process, timing
+/// the whole process.
+fn run_part(solver: &Solver, (day, part, source): Job, limits: &Limits) -> Run {
+    let path = PathBuf::from(day.dir()).join(source.file_name());
+
+    match runner::run_isolated(solver(day, part, &path), limits) {
+        Ok((verdict, elapsed)) => Run { verdict, elapsed },
+        Err(err) => Run {
+            verdict: Verdict::Failed(format!("failed to start: {}", err)),
+            elapsed: Duration::ZERO,
+        },
+    }
+}
+
+/// Answers recorded for `day` in `path` as `one: <answer>` and
+/// `two: <answer>` lines.
+fn read_answers(day: Days, path: &Path) -> HashMap<(Days, Part), String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let (part, answer) = l.split_once(':')?;
+            let part = Part::from_str(part.trim(), true).ok()?;
+            Some(((day, part), answer.trim().to_string()))
+        })
+        .collect()
+}
+
+fn write_answers(
+    day: Days,
+    recorded: &HashMap<(Days, Part), String>,
+    path: &Path,
+) -> io::Result<()> {
+    let mut out = String::new();
+    for (part, name) in [(Part::One, "one"), (Part::Two, "two")] {
+        if let Some(answer) = recorded.get(&(day, part)) {
+            out.push_str(&format!("{}: {}\n", name, answer));
+        }
+    }
+
+    fs::write(path, out)
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    const LIMITS: Limits = Limits {
+        timeout: Some(Duration::from_secs(5)),
+        memory: None,
+    };
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    /// Solves in the test process and has a child print the answer, as unit
+    /// tests can't run the binary itself.
+    fn echo_answer(day: Days, part: Part, input: &Path) -> Command {
+        let answer = day.solve(part, &fs::read_to_string(input).unwrap());
+        sh(&format!("echo {}", answer))
+    }
+
+    fn own_answers(day: Days) -> PathBuf {
+        PathBuf::from(day.dir()).join("answers.txt")
+    }
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 16)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let mut out = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                out.push_str(buffer[(x, y)].symbol());
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    #[test]
+    fn test_lists_every_day() {
+        let screen = screen(&App::with(echo_answer, LIMITS, own_answers));
+
+        for day in Days::ALL {
+            assert!(screen.contains(&format!("Day {} ", day.day_number())));
+        }
+        assert!(screen.contains(" day01/input.txt "));
+        assert!(screen.contains("Day 1 part One has not been run"));
+    }
+
+    #[test]
+    fn test_runs_example_side_by_side() {
+        let mut app = App::with(echo_answer, LIMITS, own_answers);
+        app.handle_key(KeyCode::Tab);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('2'));
+        app.handle_key(KeyCode::Enter);
+        app.wait();
+        let screen = screen(&app);
+
+        let row = screen.lines().find(|l| l.contains("Day 1 ")).unwrap();
+        assert!(row.contains("11 ") && row.contains("31 "));
+        assert!(screen.lines().nth(1).unwrap().contains("3   4"));
+        assert!(screen.contains(" day01/example.txt "));
+    }
+
+    #[test]
+    fn test_checks_recorded_answers() {
+        let dir = std::env::temp_dir().join(format!("tui_answers_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let answers = dir.join("day01.txt");
+        fs::write(&answers, "one: 11\ntwo: 30\n").unwrap();
+
+        // Stand the example in for the real input, so the test doesn't depend
+        // on whose input is checked out
+        let mut app = App::with(
+            |day, part, _| echo_answer(day, part, &PathBuf::from(day.dir()).join("example.txt")),
+            LIMITS,
+            {
+                let dir = dir.clone();
+                move |day| dir.join(format!("day{:02}.txt", day.day_number()))
+            },
+        );
+        let day = Days::ALL[0];
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('2'));
+        app.handle_key(KeyCode::Enter);
+        app.wait();
+
+        assert_eq!(app.status(day, Part::One), Some(Status::Correct));
+        assert!(screen(&app).contains("11 ✓"));
+        assert_eq!(
+            app.status(day, Part::Two),
+            Some(Status::Wrong {
+                expected: "30".to_string()
+            })
+        );
+        assert!(screen(&app).contains("31 (recorded answer is 30)"));
+
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.status(day, Part::Two), Some(Status::Correct));
+        assert_eq!(fs::read_to_string(&answers).unwrap(), "one: 11\ntwo: 31\n");
+
+        assert_eq!(app.status(Days::ALL[1], Part::One), None);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char('1'));
+        app.handle_key(KeyCode::Enter);
+        app.wait();
+        assert_eq!(
+            app.status(Days::ALL[1], Part::One),
+            Some(Status::Unrecorded)
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failures_stay_in_their_process() {
+        let panic = "echo \"thread 'main' panicked at src/days/day07/mod.rs:1:1:\" >&2; \
+                     echo 'malformed line' >&2; exit 101";
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(200)),
+            memory: None,
+        };
+        let mut app = App::with(
+            move |_, part, _| match part {
+                Part::One => sh(panic),
+                Part::Two => sh("exec sleep 5"),
+            },
+            limits,
+            own_answers,
+        );
+
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('2'));
+        app.handle_key(KeyCode::Enter);
+        assert!(screen(&app).contains("running..."));
+        app.wait();
+
+        let screen = screen(&app);
+        let row = screen.lines().find(|l| l.contains("Day 1 ")).unwrap();
+        assert!(row.contains("panicked") && row.contains("timed out"));
+        assert!(screen.contains("timed out"));
+        app.handle_key(KeyCode::Char('1'));
+        assert_eq!(app.details(), "panicked: malformed line");
+    }
+}
@@ -0,0 +1,278 @@
+use super::export::ColourMap;
+use super::grid::Grid;
+use super::raster::Rgb;
+use clap::ValueEnum;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, terminal};
+use std::collections::HashSet;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+/// One picture of a grid puzzle in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub grid: Grid<char>,
+    /// Cells to draw attention to, e.g. the latest match
+    pub highlighted: HashSet<(usize, usize)>,
+    /// A line shown under the grid
+    pub caption: String,
+}
+
+/// Somewhere a day can send frames to.
+pub trait FrameSink {
+    fn emit(&mut self, frame: Frame);
+
+    /// Called once the day has emitted its last frame.
+    fn finish(&mut self) {}
+}
+
+/// Keeps every frame, for tests and for exporting.
+#[derive(Debug, Default)]
+pub struct Capture {
+    pub frames: Vec<Frame>,
+}
+
+impl FrameSink for Capture {
+    fn emit(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Palette {
+    /// Colours for a dark terminal background
+    Dark,
+    /// Colours for a light terminal background
+    Light,
+    /// No colours, highlighted cells are shown in reverse video
+    Mono,
+}
+
+impl Palette {
+    /// 256-colour foreground for `ch`, if any. Characters the day gives their
+    /// own colour in `colours` keep it; the rest are drawn as floor or label.
+    fn colour(self, ch: char, colours: &ColourMap) -> Option<u8> {
+        let (floor, label) = match self {
+            Palette::Dark => (238, 48),
+            Palette::Light => (250, 28),
+            Palette::Mono => return None,
+        };
+        if let Some(colour) = colours.char_colour(ch) {
+            return Some(xterm(colour));
+        }
+
+        match ch {
+            '.' => Some(floor),
+            ch if ch.is_alphanumeric() => Some(label),
+            _ => None,
+        }
+    }
+
+    fn highlight(self) -> &'static str {
+        match self {
+            Palette::Dark => "\x1b[1;48;5;88m",
+            Palette::Light => "\x1b[1;48;5;223m",
+            Palette::Mono => "\x1b[7m",
+        }
+    }
+}
+
+/// The nearest colour to `colour` in the 256-colour 6x6x6 cube.
+fn xterm([r, g, b]: Rgb) -> u8 {
+    let level = |c: u8| match c {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (c - 35) / 40,
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Draws `frame` with ANSI escape codes, one `\r\n` terminated line per row
+/// followed by the caption.
+pub fn render_ansi(frame: &Frame, palette: Palette, colours: &ColourMap) -> String {
+    let mut out = String::new();
+
+    for row in 0..frame.grid.height() {
+        for col in 0..frame.grid.width() {
+            let ch = frame.grid[(row, col)];
+            let highlighted = frame.highlighted.contains(&(row, col));
+            let colour = palette.colour(ch, colours);
+
+            if highlighted {
+                out.push_str(palette.highlight());
+            }
+            if let Some(colour) = colour {
+                out.push_str(&format!("\x1b[38;5;{}m", colour));
+            }
+            out.push(ch);
+            if highlighted || colour.is_some() {
+                out.push_str("\x1b[0m");
+            }
+        }
+        out.push_str("\r\n");
+    }
+    out.push_str(&frame.caption);
+    out.push_str("\r\n");
+
+    out
+}
+
+/// Plays frames in the terminal's alternate screen. Space pauses, `n` or the
+/// right arrow steps while paused, `+` and `-` change the speed and `q` stops.
+pub struct Terminal {
+    out: Stdout,
+    palette: Palette,
+    colours: ColourMap,
+    frame_time: Duration,
+    every: usize,
+    seen: usize,
+    /// The last frame skipped because of `every`, shown when finishing
+    pending: Option<Frame>,
+    paused: bool,
+    quit: bool,
+}
+
+impl Terminal {
+    /// Takes over the terminal until dropped. Only every `every`th frame is
+    /// shown, at up to `fps` frames per second.
+    pub fn new(palette: Palette, colours: ColourMap, fps: u32, every: usize) -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(Terminal {
+            out,
+            palette,
+            colours,
+            frame_time: Duration::from_secs(1) / fps.max(1),
+            every: every.max(1),
+            seen: 0,
+            pending: None,
+            paused: false,
+            quit: false,
+        })
+    }
+
+    fn show(&mut self, frame: &Frame) -> io::Result<()> {
+        let status = format!(
+            "frame {}  {}  [space] pause  [n] step  [+/-] speed  [q] quit",
+            self.seen,
+            if self.paused { "paused " } else { "playing" }
+        );
+        write!(
+            self.out,
+            "\x1b[H\x1b[2J{}{}\r\n",
+            render_ansi(frame, self.palette, &self.colours),
+            status
+        )?;
+        self.out.flush()
+    }
+
+    /// Waits out the rest of the frame, or until stepped while paused.
+    fn wait(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + self.frame_time;
+
+        loop {
+            let timeout = if self.paused {
+                Duration::from_secs(3600)
+            } else {
+                deadline.saturating_duration_since(Instant::now())
+            };
+            if !event::poll(timeout)? {
+                if self.paused {
+                    continue;
+                }
+                return Ok(());
+            }
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.quit = true;
+                    return Ok(());
+                }
+                KeyCode::Char(' ') => self.paused = !self.paused,
+                KeyCode::Char('n') | KeyCode::Right if self.paused => return Ok(()),
+                KeyCode::Char('+') => self.frame_time /= 2,
+                KeyCode::Char('-') => {
+                    self.frame_time = (self.frame_time * 2).min(Duration::from_secs(2))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl FrameSink for Terminal {
+    fn emit(&mut self, frame: Frame) {
+        if self.quit {
+            return;
+        }
+
+        self.seen += 1;
+        if !(self.seen - 1).is_multiple_of(self.every) {
+            self.pending = Some(frame);
+            return;
+        }
+
+        self.pending = None;
+        if self.show(&frame).and_then(|_| self.wait()).is_err() {
+            self.quit = true;
+        }
+    }
+
+    /// Shows the final frame and waits for `q`.
+    fn finish(&mut self) {
+        if let Some(frame) = self.pending.take() {
+            if self.quit || self.show(&frame).is_err() {
+                return;
+            }
+        }
+
+        self.paused = true;
+        while !self.quit {
+            if self.wait().is_err() {
+                break;
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod animation_tests {
+    use super::*;
+
+    #[test]
+    fn test_render_ansi() {
+        let frame = Frame {
+            grid: Grid::parse("#.\n^x").unwrap(),
+            highlighted: HashSet::from([(1, 1)]),
+            caption: "step 1".to_string(),
+        };
+
+        let colours = ColourMap::default().with("#", [96, 96, 112]);
+
+        assert_eq!(
+            render_ansi(&frame, Palette::Mono, &colours),
+            "#.\r\n^\x1b[7mx\x1b[0m\r\nstep 1\r\n"
+        );
+        assert_eq!(
+            render_ansi(&frame, Palette::Dark, &colours),
+            "\x1b[38;5;59m#\x1b[0m\x1b[38;5;238m.\x1b[0m\r\n\
+             ^\x1b[1;48;5;88m\x1b[38;5;48mx\x1b[0m\r\nstep 1\r\n"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// Integer types a column can hold. Metrics are computed in `i128`.
+pub trait Integer: Copy + Ord + Hash + fmt::Debug + FromStr + Into<i128> {}
+
+impl<T: Copy + Ord + Hash + fmt::Debug + FromStr + Into<i128>> Integer for T {}
+
+/// Whitespace separated numeric columns, one row per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Columns<T> {
+    columns: Vec<Vec<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    WrongWidth {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "input has no rows"),
+            ParseError::WrongWidth {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} columns, found {}",
+                line, expected, found
+            ),
+            ParseError::InvalidNumber {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}, column {}: '{}' is not a valid number",
+                line, column, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl<T: Integer> Columns<T> {
+    /// Parses every non-blank line. The first row decides how many columns
+    /// there are, every other row must have as many.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut columns: Vec<Vec<T>> = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            let first_row = columns.is_empty();
+            let mut found = 0;
+
+            for (column, field) in line.split_whitespace().enumerate() {
+                let value = field.parse().map_err(|_| ParseError::InvalidNumber {
+                    line: i + 1,
+                    column: column + 1,
+                    value: field.to_string(),
+                })?;
+
+                if first_row {
+                    columns.push(vec![]);
+                }
+                if let Some(values) = columns.get_mut(column) {
+                    values.push(value);
+                }
+                found += 1;
+            }
+
+            if found > 0 && found != columns.len() {
+                return Err(ParseError::WrongWidth {
+                    line: i + 1,
+                    expected: columns.len(),
+                    found,
+                });
+            }
+        }
+
+        if columns.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        Ok(Columns { columns })
+    }
+
+    pub fn count(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn column(&self, index: usize) -> Option<&[T]> {
+        self.columns.get(index).map(Vec::as_slice)
+    }
+
+    pub fn into_columns(self) -> Vec<Vec<T>> {
+        self.columns
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// Sum of differences between the columns after sorting both
+    Distance,
+    /// Sum of each left value times how often it occurs on the right
+    Similarity,
+    /// Number of values the columns have in common, counting repeats
+    Intersection,
+    /// Kendall's tau-b rank correlation of the rows as given
+    KendallTau,
+    /// Spearman's rank correlation of the rows as given
+    Spearman,
+}
+
+/// How the distance and similarity metrics are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Solver {
+    /// Comparison sort and hashing, works for any integer column
+    Comparison,
+    /// Radix sort and counting arrays, for small non-negative values only
+    Counting,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricValue {
+    Integer(i128),
+    Real(f64),
+}
+
+impl fmt::Display for MetricValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricValue::Integer(value) => write!(f, "{}", value),
+            MetricValue::Real(value) => write!(f, "{:.6}", value),
+        }
+    }
+}
+
+impl Metric {
+    pub fn compute<T: Integer>(self, left: &[T], right: &[T]) -> MetricValue {
+        match self {
+            Metric::Distance => MetricValue::Integer(sorted_distance(left, right)),
+            Metric::Similarity => MetricValue::Integer(similarity(left, right)),
+            Metric::Intersection => MetricValue::Integer(intersection(left, right) as i128),
+            Metric::KendallTau => MetricValue::Real(kendall_tau(left, right)),
+            Metric::Spearman => MetricValue::Real(spearman(left, right)),
+        }
+    }
+
+    /// Same as [`Metric::compute`], using [`Solver::Counting`] where it
+    /// applies.
+    pub fn compute_counting(self, left: &[u32], right: &[u32]) -> MetricValue {
+        match self {
+            Metric::Distance => MetricValue::Integer(sorted_distance_radix(left, right)),
+            Metric::Similarity => MetricValue::Integer(similarity_counting(left, right)),
+            _ => self.compute(left, right),
+        }
+    }
+}
+
+pub fn sorted_distance<T: Integer>(left: &[T], right: &[T]) -> i128 {
+    let mut left = left.to_vec();
+    let mut right = right.to_vec();
+    left.sort_unstable();
+    right.sort_unstable();
+
+    left.into_iter()
+        .zip(right)
+        .map(|(l, r)| (l.into() - r.into()).abs())
+        .sum()
+}
+
+pub fn similarity<T: Integer>(left: &[T], right: &[T]) -> i128 {
+    let right_counts = counts(right);
+
+    left.iter()
+        .map(|num| (*num).into() * *right_counts.get(num).unwrap_or(&0) as i128)
+        .sum()
+}
+
+/// [`sorted_distance`] using an LSD radix sort, one pass per byte of the
+/// largest value.
+pub fn sorted_distance_radix(left: &[u32], right: &[u32]) -> i128 {
+    let mut left = left.to_vec();
+    let mut right = right.to_vec();
+    let mut buffer = vec![0; left.len().max(right.len())];
+    radix_sort(&mut left, &mut buffer);
+    radix_sort(&mut right, &mut buffer);
+
+    left.into_iter()
+        .zip(right)
+        .map(|(l, r)| l.abs_diff(r) as i128)
+        .sum()
+}
+
+/// Largest value [`similarity_counting`] allocates a counting array for,
+/// comfortably above the five-digit puzzle inputs while keeping the array
+/// at half a megabyte.
+const MAX_COUNTED_VALUE: u32 = 1 << 17;
+
+/// [`similarity`] counting into an array indexed by value rather than a hash
+/// map. Falls back to hashing when values are too large for that.
+pub fn similarity_counting(left: &[u32], right: &[u32]) -> i128 {
+    let max = right.iter().copied().max().unwrap_or(0);
+    if max > MAX_COUNTED_VALUE {
+        return similarity(left, right);
+    }
+
+    let mut right_counts = vec![0u32; max as usize + 1];
+    for &num in right {
+        right_counts[num as usize] += 1;
+    }
+
+    left.iter()
+        .map(|&num| {
+            let count = right_counts.get(num as usize).copied().unwrap_or(0);
+            num as i128 * count as i128
+        })
+        .sum()
+}
+
+fn radix_sort(values: &mut [u32], buffer: &mut [u32]) {
+    let max = values.iter().copied().max().unwrap_or(0);
+    let buffer = &mut buffer[..values.len()];
+    let mut shift = 0;
+
+    while shift < u32::BITS && max >> shift > 0 {
+        let mut offsets = [0usize; 256];
+        for &value in values.iter() {
+            offsets[(value >> shift) as usize & 0xff] += 1;
+        }
+
+        let mut total = 0;
+        for offset in offsets.iter_mut() {
+            (*offset, total) = (total, total + *offset);
+        }
+
+        for &value in values.iter() {
+            let digit = (value >> shift) as usize & 0xff;
+            buffer[offsets[digit]] = value;
+            offsets[digit] += 1;
+        }
+
+        values.copy_from_slice(buffer);
+        shift += 8;
+    }
+}
+
+pub fn intersection<T: Integer>(left: &[T], right: &[T]) -> usize {
+    let right_counts = counts(right);
+
+    counts(left)
+        .iter()
+        .map(|(num, &count)| count.min(*right_counts.get(num).unwrap_or(&0)))
+        .sum()
+}
+
+/// Tau-b, which corrects for ties. `NaN` if either column is constant.
+pub fn kendall_tau<T: Integer>(left: &[T], right: &[T]) -> f64 {
+    let (mut concordant, mut discordant) = (0i64, 0i64);
+    let (mut tied_left, mut tied_right) = (0i64, 0i64);
+
+    let rows: Vec<(T, T)> = left.iter().copied().zip(right.iter().copied()).collect();
+    for (i, &(l1, r1)) in rows.iter().enumerate() {
+        for &(l2, r2) in &rows[i + 1..] {
+            let (dl, dr) = (l1.cmp(&l2), r1.cmp(&r2));
+            if dl.is_eq() {
+                tied_left += 1;
+            }
+            if dr.is_eq() {
+                tied_right += 1;
+            }
+            if dl.is_ne() && dr.is_ne() {
+                if dl == dr {
+                    concordant += 1;
+                } else {
+                    discordant += 1;
+                }
+            }
+        }
+    }
+
+    let pairs = (rows.len() * rows.len().saturating_sub(1) / 2) as i64;
+    let denominator = (((pairs - tied_left) * (pairs - tied_right)) as f64).sqrt();
+
+    (concordant - discordant) as f64 / denominator
+}
+
+/// Pearson correlation of the ranks, with tied values sharing their average
+/// rank. `NaN` if either column is constant.
+pub fn spearman<T: Integer>(left: &[T], right: &[T]) -> f64 {
+    let len = left.len().min(right.len());
+    let (left, right) = (ranks(&left[..len]), ranks(&right[..len]));
+    let mean = (len as f64 + 1.0) / 2.0;
+
+    let (mut covariance, mut variance_left, mut variance_right) = (0.0, 0.0, 0.0);
+    for (l, r) in left.into_iter().zip(right) {
+        covariance += (l - mean) * (r - mean);
+        variance_left += (l - mean).powi(2);
+        variance_right += (r - mean).powi(2);
+    }
+
+    covariance / (variance_left * variance_right).sqrt()
+}
+
+fn counts<T: Integer>(values: &[T]) -> HashMap<T, usize> {
+    values
+        .iter()
+        .fold(HashMap::with_capacity(values.len()), |mut map, num| {
+            *map.entry(*num).or_insert(0) += 1;
+            map
+        })
+}
+
+/// 1-based ranks, averaged over ties.
+fn ranks<T: Integer>(values: &[T]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_unstable_by_key(|&i| values[i]);
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let value = values[order[start]];
+        let end = start
+            + order[start..]
+                .iter()
+                .take_while(|&&i| values[i] == value)
+                .count();
+
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+
+    ranks
+}
+
+#[cfg(test)]
+mod columns_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_columns() {
+        let columns = Columns::<i64>::parse("1 2 3\n4 5 6\n\n").unwrap();
+
+        assert_eq!(columns.count(), 3);
+        assert_eq!(columns.column(2), Some(&[3, 6][..]));
+        assert_eq!(
+            Columns::<u8>::parse("1 2\n3\n"),
+            Err(ParseError::WrongWidth {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Columns::<u8>::parse("1 256"),
+            Err(ParseError::InvalidNumber {
+                line: 1,
+                column: 2,
+                value: "256".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_metrics() {
+        let left = [3, 4, 2, 1, 3, 3];
+        let right = [4, 3, 5, 3, 9, 3];
+
+        assert_eq!(sorted_distance(&left, &right), 11);
+        assert_eq!(similarity(&left, &right), 31);
+        assert_eq!(intersection(&left, &right), 4);
+        assert_eq!(sorted_distance_radix(&left, &right), 11);
+        assert_eq!(similarity_counting(&left, &right), 31);
+    }
+
+    #[test]
+    fn test_similarity_counting_falls_back_above_the_cap() {
+        let left = [3, MAX_COUNTED_VALUE + 1, u32::MAX];
+        let right = [3, MAX_COUNTED_VALUE + 1, MAX_COUNTED_VALUE + 1, u32::MAX];
+
+        assert_eq!(
+            similarity_counting(&left, &right),
+            similarity(&left, &right)
+        );
+    }
+
+    #[test]
+    fn test_radix_sort() {
+        let mut values = vec![70_000, 3, 65_536, 255, 256, 0, 99_999, 3, 1 << 31];
+        let mut expected = values.clone();
+        expected.sort_unstable();
+
+        radix_sort(&mut values, &mut [0; 9]);
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_rank_correlations() {
+        let x = [1, 2, 3, 4];
+        let y = [10, 20, 30, 40];
+        let reversed = [40, 30, 20, 10];
+
+        assert_eq!(kendall_tau(&x, &y), 1.0);
+        assert_eq!(kendall_tau(&x, &reversed), -1.0);
+        assert_eq!(spearman(&x, &y), 1.0);
+        assert_eq!(spearman(&x, &reversed), -1.0);
+        assert_eq!(ranks(&[5, 1, 5, 3]), vec![3.5, 1.0, 3.5, 2.0]);
+        assert!((kendall_tau(&[1, 2, 2, 3], &[1, 3, 2, 4]) - 0.912870929).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Shape of the sequence `x0, f(x0), f(f(x0)), ...` once it starts repeating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Index of the first state that is part of the cycle (mu).
+    pub start: usize,
+    /// Number of distinct states in the cycle (lambda).
+    pub length: usize,
+}
+
+/// Floyd's tortoise and hare.
+///
+/// `step` returns `None` when the sequence terminates (e.g. the guard walks
+/// off the map), in which case there is no cycle.
+pub fn floyd<S, F>(initial: S, mut step: F) -> Option<Cycle>
+where
+    S: Clone + PartialEq,
+    F: FnMut(&S) -> Option<S>,
+{
+    let mut tortoise = step(&initial)?;
+    let mut hare = step(&tortoise)?;
+    while tortoise != hare {
+        tortoise = step(&tortoise)?;
+        hare = step(&hare)?;
+        hare = step(&hare)?;
+    }
+
+    let mut start = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise)?;
+        hare = step(&hare)?;
+        start += 1;
+    }
+
+    let mut length = 1;
+    hare = step(&tortoise)?;
+    while tortoise != hare {
+        hare = step(&hare)?;
+        length += 1;
+    }
+
+    Some(Cycle { start, length })
+}
+
+/// Brent's algorithm. Same contract as [`floyd`], but evaluates `step` fewer
+/// times on average.
+pub fn brent<S, F>(initial: S, mut step: F) -> Option<Cycle>
+where
+    S: Clone + PartialEq,
+    F: FnMut(&S) -> Option<S>,
+{
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial)?;
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare)?;
+        length += 1;
+    }
+
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..length {
+        hare = step(&hare)?;
+    }
+
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise)?;
+        hare = step(&hare)?;
+        start += 1;
+    }
+
+    Some(Cycle { start, length })
+}
+
+/// Detects a cycle by remembering the index at which every state was first
+/// seen. Uses O(mu + lambda) memory but evaluates `step` exactly once per
+/// distinct state.
+pub fn find_cycle<S, F>(initial: S, mut step: F) -> Option<Cycle>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> Option<S>,
+{
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut state = initial;
+
+    for index in 0.. {
+        if let Some(&start) = seen.get(&state) {
+            return Some(Cycle {
+                start,
+                length: index - start,
+            });
+        }
+
+        let next = step(&state)?;
+        seen.insert(state, index);
+        state = next;
+    }
+
+    unreachable!()
+}
+
+/// Returns the state after applying `step` `n` times, skipping over whole
+/// cycles once a repeated state is found. Intended for puzzles asking for the
+/// state after a billion iterations.
+pub fn nth_state<S, F>(initial: S, n: usize, mut step: F) -> S
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> S,
+{
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut history: Vec<S> = vec![];
+    let mut state = initial;
+
+    for index in 0..n {
+        if let Some(&start) = seen.get(&state) {
+            let length = index - start;
+            return history[start + (n - start) % length].clone();
+        }
+
+        seen.insert(state.clone(), index);
+        history.push(state.clone());
+        state = step(&state);
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod cycle_tests {
+    use super::*;
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 3 -> ...
+    fn rho(x: &u32) -> Option<u32> {
+        Some(if *x == 6 { 3 } else { x + 1 })
+    }
+
+    #[test]
+    fn test_detectors_agree() {
+        let expected = Some(Cycle {
+            start: 3,
+            length: 4,
+        });
+
+        assert_eq!(floyd(0, rho), expected);
+        assert_eq!(brent(0, rho), expected);
+        assert_eq!(find_cycle(0, rho), expected);
+    }
+
+    #[test]
+    fn test_terminating_sequence_has_no_cycle() {
+        let step = |x: &u32| if *x < 10 { Some(x + 1) } else { None };
+
+        assert_eq!(floyd(0, step), None);
+        assert_eq!(brent(0, step), None);
+        assert_eq!(find_cycle(0, step), None);
+    }
+
+    #[test]
+    fn test_nth_state_skips_cycles() {
+        let step = |x: &u32| rho(x).unwrap();
+        let brute_force = (0..1_000).fold(0, |x, _| step(&x));
+
+        assert_eq!(nth_state(0, 1_000, step), brute_force);
+        assert_eq!(
+            nth_state(0, 1_000_000_000, step),
+            3 + (1_000_000_000 - 3) % 4
+        );
+        assert_eq!(nth_state(0, 2, step), 2);
+    }
+}
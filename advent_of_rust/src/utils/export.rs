@@ -0,0 +1,324 @@
+use super::animation::{Frame, FrameSink};
+use super::grid::Grid;
+use super::raster::{Image, Rgb};
+use super::svg;
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+/// Colours for drawing frames as images: one per character, with fallbacks
+/// for `.` and whitespace (background) and everything else (foreground).
+/// Highlighted cells are always drawn in the highlight colour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColourMap {
+    background: Rgb,
+    foreground: Rgb,
+    highlight: Rgb,
+    chars: HashMap<char, Rgb>,
+}
+
+impl Default for ColourMap {
+    fn default() -> Self {
+        ColourMap {
+            background: [15, 15, 35],
+            foreground: [150, 150, 170],
+            highlight: [255, 255, 255],
+            chars: HashMap::new(),
+        }
+    }
+}
+
+impl ColourMap {
+    /// Draws each of `chars` in `colour`.
+    pub fn with(mut self, chars: &str, colour: Rgb) -> Self {
+        self.chars.extend(chars.chars().map(|ch| (ch, colour)));
+        self
+    }
+
+    pub fn apply(&mut self, rule: &ColourRule) {
+        match rule.target {
+            ColourTarget::Char(ch) => {
+                self.chars.insert(ch, rule.colour);
+            }
+            ColourTarget::Background => self.background = rule.colour,
+            ColourTarget::Foreground => self.foreground = rule.colour,
+            ColourTarget::Highlight => self.highlight = rule.colour,
+        }
+    }
+
+    /// The colour `ch` was given its own colour with [`ColourMap::with`] or a
+    /// rule, if any.
+    pub fn char_colour(&self, ch: char) -> Option<Rgb> {
+        self.chars.get(&ch).copied()
+    }
+
+    pub fn colour(&self, ch: char, highlighted: bool) -> Rgb {
+        if highlighted {
+            return self.highlight;
+        }
+
+        match self.chars.get(&ch) {
+            Some(&colour) => colour,
+            None if ch == '.' || ch.is_whitespace() => self.background,
+            None => self.foreground,
+        }
+    }
+
+    /// Every colour a frame can be drawn in, for formats that need their
+    /// palette up front.
+    pub fn palette(&self) -> Vec<Rgb> {
+        let mut chars: Vec<Rgb> = self.chars.values().copied().collect();
+        chars.sort_unstable();
+
+        let mut palette = vec![self.background, self.foreground, self.highlight];
+        for colour in chars {
+            if !palette.contains(&colour) {
+                palette.push(colour);
+            }
+        }
+
+        palette
+    }
+
+    fn cells(&self, frame: &Frame) -> Grid<Rgb> {
+        let grid = &frame.grid;
+
+        Grid::from_fn(grid.height(), grid.width(), |pos| {
+            self.colour(grid[pos], frame.highlighted.contains(&pos))
+        })
+    }
+}
+
+/// What a [`ColourRule`] recolours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColourTarget {
+    Char(char),
+    Background,
+    Foreground,
+    Highlight,
+}
+
+/// A colour override written `target=rrggbb`, where the target is a single
+/// character or one of `background`, `foreground` and `highlight`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColourRule {
+    pub target: ColourTarget,
+    pub colour: Rgb,
+}
+
+impl FromStr for ColourRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split on the last '=' so that '=' itself can be recoloured
+        let (target, hex) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected target=rrggbb, got '{}'", s))?;
+
+        let target = match target {
+            "background" => ColourTarget::Background,
+            "foreground" => ColourTarget::Foreground,
+            "highlight" => ColourTarget::Highlight,
+            _ => {
+                let mut chars = target.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => ColourTarget::Char(ch),
+                    _ => return Err(format!("unknown colour target '{}'", target)),
+                }
+            }
+        };
+
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return Err(format!("expected a colour like e62828, got '{}'", hex));
+        }
+        let colour = u32::from_str_radix(hex, 16).unwrap();
+
+        Ok(ColourRule {
+            target,
+            colour: [(colour >> 16) as u8, (colour >> 8) as u8, colour as u8],
+        })
+    }
+}
+
+/// Passes every `step`th frame, and the last one, to `write` as they arrive,
+/// so exporting a long animation only ever holds one frame. Stops writing at
+/// the first error.
+pub struct Exporter<F> {
+    write: F,
+    step: usize,
+    seen: usize,
+    written: usize,
+    /// The latest frame if it was skipped, in case it turns out to be the last
+    skipped: Option<Frame>,
+    error: Option<io::Error>,
+}
+
+impl<F: FnMut(Frame) -> io::Result<()>> Exporter<F> {
+    pub fn new(step: usize, write: F) -> Self {
+        Exporter {
+            write,
+            step: step.max(1),
+            seen: 0,
+            written: 0,
+            skipped: None,
+            error: None,
+        }
+    }
+
+    /// How many frames were written, or the error that stopped the export.
+    pub fn result(self) -> io::Result<usize> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.written),
+        }
+    }
+
+    fn write(&mut self, frame: Frame) {
+        if self.error.is_none() {
+            match (self.write)(frame) {
+                Ok(()) => self.written += 1,
+                Err(err) => self.error = Some(err),
+            }
+        }
+    }
+}
+
+impl<F: FnMut(Frame) -> io::Result<()>> FrameSink for Exporter<F> {
+    fn emit(&mut self, frame: Frame) {
+        let index = self.seen;
+        self.seen += 1;
+
+        if index.is_multiple_of(self.step) {
+            self.skipped = None;
+            self.write(frame);
+        } else {
+            self.skipped = Some(frame);
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(frame) = self.skipped.take() {
+            self.write(frame);
+        }
+    }
+}
+
+/// Draws `frame` with every cell a `cell_size` pixel square.
+pub fn image(frame: &Frame, cell_size: usize, colours: &ColourMap) -> Image {
+    Image::from_grid(&colours.cells(frame), cell_size, |&colour| colour)
+}
+
+/// Draws `frame` as an SVG document, with every cell filled in its colour and
+/// its character written on top.
+pub fn svg(frame: &Frame, cell_size: usize, colours: &ColourMap) -> String {
+    let fills = colours
+        .cells(frame)
+        .map(|&[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b));
+
+    svg::from_grid(&frame.grid, cell_size, |pos| Some(&fills[pos]))
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn frame() -> Frame {
+        Frame {
+            grid: Grid::parse("#.\nx^").unwrap(),
+            highlighted: HashSet::from([(0, 1)]),
+            caption: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_colour_rules() {
+        assert_eq!(
+            "x=e62828".parse(),
+            Ok(ColourRule {
+                target: ColourTarget::Char('x'),
+                colour: [230, 40, 40]
+            })
+        );
+        assert_eq!(
+            "==#00ff00".parse::<ColourRule>().unwrap().target,
+            ColourTarget::Char('=')
+        );
+        assert_eq!(
+            "highlight=ffffff".parse::<ColourRule>().unwrap().target,
+            ColourTarget::Highlight
+        );
+        assert!("xy=ffffff".parse::<ColourRule>().is_err());
+        assert!("x=+fffff".parse::<ColourRule>().is_err());
+        assert!("x".parse::<ColourRule>().is_err());
+    }
+
+    #[test]
+    fn test_image_uses_colour_map() {
+        let mut colours = ColourMap::default().with("x", [1, 2, 3]);
+        colours.apply(&"highlight=0a0b0c".parse().unwrap());
+        let image = image(&frame(), 2, &colours);
+
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.pixel(0, 0), [150, 150, 170]);
+        assert_eq!(image.pixel(3, 1), [10, 11, 12]);
+        assert_eq!(image.pixel(1, 3), [1, 2, 3]);
+        assert_eq!(image.pixel(2, 2), [150, 150, 170]);
+    }
+
+    #[test]
+    fn test_palette_covers_every_colour() {
+        let colours = ColourMap::default()
+            .with("x", [1, 2, 3])
+            .with("#^", [4, 5, 6]);
+        let palette = colours.palette();
+
+        for ch in "#.x^O|? ".chars() {
+            assert!(palette.contains(&colours.colour(ch, false)), "{:?}", ch);
+        }
+        assert!(palette.contains(&colours.colour('.', true)));
+        assert_eq!(palette.len(), 5);
+    }
+
+    #[test]
+    fn test_exporter_keeps_every_step_and_the_last() {
+        let export = |count: usize, step: usize| {
+            let mut captions = vec![];
+            let mut exporter = Exporter::new(step, |frame: Frame| {
+                captions.push(frame.caption);
+                Ok(())
+            });
+            for i in 0..count {
+                exporter.emit(Frame {
+                    caption: i.to_string(),
+                    ..frame()
+                });
+            }
+            exporter.finish();
+            let written = exporter.result().unwrap();
+
+            assert_eq!(written, captions.len());
+            captions
+        };
+
+        assert_eq!(export(10, 3), ["0", "3", "6", "9"]);
+        assert_eq!(export(11, 3), ["0", "3", "6", "9", "10"]);
+        assert_eq!(export(1, 5), ["0"]);
+        assert!(export(0, 5).is_empty());
+
+        let mut exporter = Exporter::new(1, |_| Err(io::Error::other("disk full")));
+        exporter.emit(frame());
+        exporter.emit(frame());
+        assert_eq!(exporter.result().unwrap_err().to_string(), "disk full");
+    }
+
+    #[test]
+    fn test_svg_fills_every_cell() {
+        let svg = svg(&frame(), 10, &ColourMap::default());
+
+        assert!(svg.contains(r##"<rect x="0" y="0" width="10" height="10" fill="#9696aa"/>"##));
+        assert!(svg.contains(r##"<rect x="10" y="0" width="10" height="10" fill="#ffffff"/>"##));
+        assert_eq!(svg.matches("<text").count(), 3);
+    }
+}
@@ -0,0 +1,226 @@
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+use std::ops::{Index, IndexMut};
+
+/// A rectangular, row-major grid of cells addressed by `(row, col)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "grid has no rows"),
+            GridError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells but the first row has {}",
+                row, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Highlight {
+    /// Colour highlighted cells with ANSI escape codes
+    Ansi,
+    /// Replace every other cell with '.', as the puzzle examples do
+    Plain,
+}
+
+impl Grid<char> {
+    /// Parses one row per line. Trailing whitespace and trailing blank lines
+    /// are ignored, every other row must be as wide as the first.
+    pub fn parse(input: &str) -> Result<Self, GridError> {
+        let rows: Vec<&str> = input.trim_end().lines().map(str::trim_end).collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(GridError::Empty);
+        }
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (row, line) in rows.iter().enumerate() {
+            let before = cells.len();
+            cells.extend(line.chars());
+
+            let found = cells.len() - before;
+            if found != width {
+                return Err(GridError::Ragged {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+        }
+
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells,
+        })
+    }
+
+    /// Reprints the grid with the cells in `highlighted` made to stand out.
+    pub fn render(&self, highlighted: &HashSet<(usize, usize)>, highlight: Highlight) -> String {
+        let mut out = String::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let ch = self[(row, col)];
+                match (highlighted.contains(&(row, col)), highlight) {
+                    (true, Highlight::Ansi) => out.push_str(&format!("\x1b[1;31m{}\x1b[0m", ch)),
+                    (false, Highlight::Plain) => out.push('.'),
+                    _ => out.push(ch),
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// The grid as it was parsed, one row per line.
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                f.write_char(self[(row, col)])?;
+            }
+            f.write_char('\n')?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(height: usize, width: usize, mut f: impl FnMut((usize, usize)) -> T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: (0..height)
+                .flat_map(|row| (0..width).map(move |col| (row, col)))
+                .map(&mut f)
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row < self.height && col < self.width {
+            Some(&self.cells[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+    /// Moves `(row, col)` by `(d_row, d_col)`, returning `None` if that leaves
+    /// the grid.
+    pub fn offset(
+        &self,
+        (row, col): (usize, usize),
+        (d_row, d_col): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let row = row.checked_add_signed(d_row)?;
+        let col = col.checked_add_signed(d_col)?;
+
+        (row < self.height && col < self.width).then_some((row, col))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(col < self.width, "column {} out of bounds", col);
+        &self.cells[row * self.width + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(col < self.width, "column {} out of bounds", col);
+        &mut self.cells[row * self.width + col]
+    }
+}
+
+#[cfg(test)]
+mod grid_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ignores_trailing_whitespace() {
+        let grid = Grid::parse("ab \ncd\n\n").unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(grid[(1, 0)], 'c');
+        assert_eq!(grid.offset((1, 1), (0, 1)), None);
+        assert_eq!(grid.offset((1, 1), (-1, -1)), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_parse_rejects_ragged_rows() {
+        assert_eq!(
+            Grid::parse("abc\nab\nabc"),
+            Err(GridError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(Grid::parse("\n\n"), Err(GridError::Empty));
+    }
+
+    #[test]
+    fn test_render() {
+        let grid = Grid::parse("ab\ncd").unwrap();
+        let highlighted = HashSet::from([(0, 1), (1, 0)]);
+
+        assert_eq!(grid.to_string(), "ab\ncd\n");
+        assert_eq!(grid.render(&highlighted, Highlight::Plain), ".b\nc.\n");
+        assert_eq!(
+            grid.render(&highlighted, Highlight::Ansi),
+            "a\x1b[1;31mb\x1b[0m\n\x1b[1;31mc\x1b[0md\n"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod animation;
+pub mod columns;
+pub mod cycle;
+pub mod export;
+pub mod grid;
+pub mod operator_search;
+pub mod raster;
+pub mod svg;
+pub mod word_search;
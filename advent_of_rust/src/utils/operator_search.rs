@@ -0,0 +1,461 @@
+use num_bigint::BigInt;
+use num_traits::Signed;
+use std::fmt;
+
+/// A number the search can work with. Operators decide how they handle values
+/// that don't fit the type.
+pub trait Operand: Clone + Ord + fmt::Display {
+    fn is_negative(&self) -> bool;
+}
+
+impl Operand for i64 {
+    fn is_negative(&self) -> bool {
+        *self < 0
+    }
+}
+
+impl Operand for i128 {
+    fn is_negative(&self) -> bool {
+        *self < 0
+    }
+}
+
+impl Operand for BigInt {
+    fn is_negative(&self) -> bool {
+        Signed::is_negative(self)
+    }
+}
+
+/// An operator that can be placed between two operands of an equation that is
+/// evaluated strictly left to right.
+pub trait BinaryOperator<N: Operand = i64> {
+    /// Applies the operator, returning `None` if the result does not fit.
+    fn apply(&self, lhs: &N, rhs: &N) -> Option<N>;
+
+    /// Returns the `lhs` for which `apply(lhs, rhs) == result`, or `None` if
+    /// there is none or it isn't unique. Used by [`Strategy::Backward`].
+    fn invert(&self, result: &N, rhs: &N) -> Option<N>;
+
+    /// Whether `apply(lhs, rhs) == result` for every `lhs`, e.g. `0 * 0 == 0`.
+    /// [`Strategy::Backward`] then accepts any value the operands before
+    /// `rhs` evaluate to.
+    fn absorbs(&self, _result: &N, _rhs: &N) -> bool {
+        false
+    }
+
+    fn symbol(&self) -> &str;
+
+    /// Whether `apply(lhs, rhs) >= lhs` holds for every non-negative `lhs`.
+    /// When every operator agrees for all remaining operands, the search stops
+    /// exploring values that already exceed the target.
+    fn never_decreases(&self, _rhs: &N) -> bool {
+        false
+    }
+}
+
+/// A fully assigned equation, e.g. `81 + 40 * 27`.
+pub struct Expression<'a, O, N = i64> {
+    pub operands: Vec<N>,
+    pub operators: Vec<&'a O>,
+}
+
+impl<O: BinaryOperator<N>, N: Operand> fmt::Display for Expression<'_, O, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operands[0])?;
+        for (operator, operand) in self.operators.iter().zip(&self.operands[1..]) {
+            write!(f, " {} {}", operator.symbol(), operand)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Evaluates every left-to-right assignment from the first operand.
+    Forward,
+    /// Works back from the target, undoing the last operator at each step.
+    /// Far fewer branches survive, but every operator must be invertible.
+    Backward,
+}
+
+/// Search over all left-to-right operator assignments, for any operand type
+/// the operators support.
+pub struct OperatorSearch<'a, O> {
+    operators: &'a [O],
+    strategy: Strategy,
+}
+
+impl<'a, O> OperatorSearch<'a, O> {
+    pub fn new(operators: &'a [O], strategy: Strategy) -> Self {
+        Self {
+            operators,
+            strategy,
+        }
+    }
+
+    /// Returns the first assignment of operators (in the order given to
+    /// [`OperatorSearch::new`]) for which `operands` evaluates to `target`.
+    pub fn find<N>(&self, target: &N, operands: &[N]) -> Option<Expression<'a, O, N>>
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        match self.strategy {
+            Strategy::Forward => self.find_forward(target, operands),
+            Strategy::Backward => self.find_backward(target, operands),
+        }
+    }
+
+    pub fn is_solvable<N>(&self, target: &N, operands: &[N]) -> bool
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        self.find(target, operands).is_some()
+    }
+
+    /// Counts the distinct assignments of operators for which `operands`
+    /// evaluates to `target`.
+    pub fn count<N>(&self, target: &N, operands: &[N]) -> u64
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let Some((first, rest)) = operands.split_first() else {
+            return 0;
+        };
+
+        match self.strategy {
+            Strategy::Forward => self.count_forward(target, rest, &self.prunable(rest), first),
+            Strategy::Backward => self.count_backward(target, operands),
+        }
+    }
+
+    // prunable[i]: every operator is non-decreasing for all of rest[i..]
+    fn prunable<N>(&self, rest: &[N]) -> Vec<bool>
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let mut prunable = vec![true; rest.len() + 1];
+        for i in (0..rest.len()).rev() {
+            prunable[i] = prunable[i + 1]
+                && self
+                    .operators
+                    .iter()
+                    .all(|operator| operator.never_decreases(&rest[i]));
+        }
+
+        prunable
+    }
+
+    fn find_forward<N>(&self, target: &N, operands: &[N]) -> Option<Expression<'a, O, N>>
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let (first, rest) = operands.split_first()?;
+        let prunable = self.prunable(rest);
+
+        let mut chosen = Vec::with_capacity(rest.len());
+        if self.search_forward(target, rest, &prunable, first, &mut chosen) {
+            Some(Expression {
+                operands: operands.to_vec(),
+                operators: chosen,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn find_backward<N>(&self, target: &N, operands: &[N]) -> Option<Expression<'a, O, N>>
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let mut chosen = Vec::with_capacity(operands.len().saturating_sub(1));
+        if operands.is_empty() || !self.search_backward(target, operands, &mut chosen) {
+            return None;
+        }
+
+        chosen.reverse();
+        Some(Expression {
+            operands: operands.to_vec(),
+            operators: chosen,
+        })
+    }
+
+    fn search_forward<N>(
+        &self,
+        target: &N,
+        rest: &[N],
+        prunable: &[bool],
+        current: &N,
+        chosen: &mut Vec<&'a O>,
+    ) -> bool
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let Some((next, remaining)) = rest.split_first() else {
+            return current == target;
+        };
+
+        if current > target && !current.is_negative() && prunable[0] {
+            return false;
+        }
+
+        for operator in self.operators {
+            let Some(value) = operator.apply(current, next) else {
+                continue;
+            };
+
+            chosen.push(operator);
+            if self.search_forward(target, remaining, &prunable[1..], &value, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+
+        false
+    }
+
+    // `chosen` is filled from the last operator to the first
+    fn search_backward<N>(&self, target: &N, operands: &[N], chosen: &mut Vec<&'a O>) -> bool
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let (last, remaining) = operands.split_last().unwrap();
+        if remaining.is_empty() {
+            return last == target;
+        }
+
+        for operator in self.operators {
+            if operator.absorbs(target, last) {
+                let (first, rest) = remaining.split_first().unwrap();
+                let mut prefix = Vec::with_capacity(rest.len());
+                if self.evaluate_any(first, rest, &mut prefix) {
+                    chosen.push(operator);
+                    chosen.extend(prefix.into_iter().rev());
+                    return true;
+                }
+                continue;
+            }
+
+            let Some(lhs) = operator.invert(target, last) else {
+                continue;
+            };
+
+            chosen.push(operator);
+            if self.search_backward(&lhs, remaining, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+
+        false
+    }
+
+    // Finds any assignment for which `current` followed by `rest` evaluates
+    // without overflowing, whatever the result
+    fn evaluate_any<N>(&self, current: &N, rest: &[N], chosen: &mut Vec<&'a O>) -> bool
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let Some((next, remaining)) = rest.split_first() else {
+            return true;
+        };
+
+        for operator in self.operators {
+            let Some(value) = operator.apply(current, next) else {
+                continue;
+            };
+
+            chosen.push(operator);
+            if self.evaluate_any(&value, remaining, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+
+        false
+    }
+
+    fn count_forward<N>(&self, target: &N, rest: &[N], prunable: &[bool], current: &N) -> u64
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let Some((next, remaining)) = rest.split_first() else {
+            return (current == target) as u64;
+        };
+
+        if current > target && !current.is_negative() && prunable[0] {
+            return 0;
+        }
+
+        self.operators
+            .iter()
+            .filter_map(|operator| operator.apply(current, next))
+            .map(|value| self.count_forward(target, remaining, &prunable[1..], &value))
+            .sum()
+    }
+
+    fn count_backward<N>(&self, target: &N, operands: &[N]) -> u64
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let (last, remaining) = operands.split_last().unwrap();
+        if remaining.is_empty() {
+            return (last == target) as u64;
+        }
+
+        self.operators
+            .iter()
+            .map(|operator| {
+                if operator.absorbs(target, last) {
+                    let (first, rest) = remaining.split_first().unwrap();
+                    self.count_evaluations(first, rest)
+                } else {
+                    operator
+                        .invert(target, last)
+                        .map_or(0, |lhs| self.count_backward(&lhs, remaining))
+                }
+            })
+            .sum()
+    }
+
+    // Counts the assignments for which `current` followed by `rest` evaluates
+    // without overflowing
+    fn count_evaluations<N>(&self, current: &N, rest: &[N]) -> u64
+    where
+        N: Operand,
+        O: BinaryOperator<N>,
+    {
+        let Some((next, remaining)) = rest.split_first() else {
+            return 1;
+        };
+
+        self.operators
+            .iter()
+            .filter_map(|operator| operator.apply(current, next))
+            .map(|value| self.count_evaluations(&value, remaining))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod operator_search_tests {
+    use super::*;
+
+    enum Arithmetic {
+        Add,
+        Subtract,
+        Multiply,
+    }
+
+    impl BinaryOperator for Arithmetic {
+        fn apply(&self, &lhs: &i64, &rhs: &i64) -> Option<i64> {
+            match self {
+                Arithmetic::Add => lhs.checked_add(rhs),
+                Arithmetic::Subtract => lhs.checked_sub(rhs),
+                Arithmetic::Multiply => lhs.checked_mul(rhs),
+            }
+        }
+
+        fn invert(&self, &result: &i64, &rhs: &i64) -> Option<i64> {
+            match self {
+                Arithmetic::Add => result.checked_sub(rhs),
+                Arithmetic::Subtract => result.checked_add(rhs),
+                Arithmetic::Multiply => (rhs != 0 && result % rhs == 0).then(|| result / rhs),
+            }
+        }
+
+        fn absorbs(&self, &result: &i64, &rhs: &i64) -> bool {
+            matches!(self, Arithmetic::Multiply) && rhs == 0 && result == 0
+        }
+
+        fn symbol(&self) -> &str {
+            match self {
+                Arithmetic::Add => "+",
+                Arithmetic::Subtract => "-",
+                Arithmetic::Multiply => "*",
+            }
+        }
+
+        fn never_decreases(&self, &rhs: &i64) -> bool {
+            match self {
+                Arithmetic::Add => rhs >= 0,
+                Arithmetic::Subtract => rhs <= 0,
+                Arithmetic::Multiply => rhs >= 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_returns_witness() {
+        let operators = [Arithmetic::Add, Arithmetic::Multiply];
+        let search = OperatorSearch::new(&operators, Strategy::Forward);
+
+        let witness = search.find(&3267, &[81, 40, 27]).unwrap();
+        assert_eq!(witness.to_string(), "81 + 40 * 27");
+        assert_eq!(search.count(&3267, &[81, 40, 27]), 2);
+        assert!(search.find(&83, &[17, 5]).is_none());
+    }
+
+    #[test]
+    fn test_user_supplied_operators() {
+        let operators = [Arithmetic::Add, Arithmetic::Subtract, Arithmetic::Multiply];
+        let search = OperatorSearch::new(&operators, Strategy::Forward);
+
+        // 20 exceeds the target, so only a non-monotonic operator can recover
+        let witness = search.find(&7, &[20, 15, 2]).unwrap();
+        assert_eq!(witness.to_string(), "20 - 15 + 2");
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let operators = [Arithmetic::Add, Arithmetic::Subtract, Arithmetic::Multiply];
+        let forward = OperatorSearch::new(&operators, Strategy::Forward);
+        let backward = OperatorSearch::new(&operators, Strategy::Backward);
+
+        for (target, operands) in (-50..200).flat_map(|t| [(t, [3, 7, 2, 5]), (t, [3, 0, 2, 0])]) {
+            let witness = backward.find(&target, &operands);
+
+            assert_eq!(witness.is_some(), forward.is_solvable(&target, &operands));
+            assert_eq!(
+                forward.count(&target, &operands),
+                backward.count(&target, &operands)
+            );
+            if let Some(witness) = witness {
+                let value = witness
+                    .operators
+                    .iter()
+                    .zip(&operands[1..])
+                    .fold(operands[0], |acc, (operator, &n)| {
+                        operator.apply(&acc, &n).unwrap()
+                    });
+                assert_eq!(value, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow_is_a_dead_end() {
+        let operators = [Arithmetic::Multiply, Arithmetic::Add];
+        let search = OperatorSearch::new(&operators, Strategy::Forward);
+
+        assert!(search.find(&i64::MAX, &[i64::MAX, 2, 0]).is_none());
+        assert_eq!(
+            search
+                .find(&i64::MAX, &[i64::MAX - 1, 1])
+                .unwrap()
+                .to_string(),
+            format!("{} + 1", i64::MAX - 1)
+        );
+    }
+}
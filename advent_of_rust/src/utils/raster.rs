@@ -0,0 +1,248 @@
+use super::grid::Grid;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Seek, Write};
+
+pub type Rgb = [u8; 3];
+
+/// An RGB image, row-major, for exporting grid visualizations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// Draws every cell of `grid` as a `cell_size` pixel square in the colour
+    /// `colour` picks for it.
+    pub fn from_grid<T>(grid: &Grid<T>, cell_size: usize, colour: impl Fn(&T) -> Rgb) -> Image {
+        let (width, height) = (grid.width() * cell_size, grid.height() * cell_size);
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for row in 0..height {
+            pixels.extend((0..width).map(|col| colour(&grid[(row / cell_size, col / cell_size)])));
+        }
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Writes a binary (P6) PPM.
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(self.pixels.as_flattened())
+    }
+
+    pub fn write_png(&self, out: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(self.pixels.as_flattened())
+            .map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    /// Reads an 8-bit RGB PNG, such as one written by [`Image::write_png`].
+    pub fn read_png(input: impl BufRead + Seek) -> io::Result<Image> {
+        let mut reader = png::Decoder::new(input)
+            .read_info()
+            .map_err(io::Error::other)?;
+        let mut buf = vec![0; reader.output_buffer_size().unwrap_or_default()];
+        let info = reader.next_frame(&mut buf).map_err(io::Error::other)?;
+        if (info.color_type, info.bit_depth) != (png::ColorType::Rgb, png::BitDepth::Eight) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an 8-bit RGB PNG",
+            ));
+        }
+
+        Ok(Image {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels: buf[..info.buffer_size()]
+                .chunks_exact(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                .collect(),
+        })
+    }
+}
+
+/// Writes frames to a looping GIF one at a time, so they needn't all be held
+/// in memory. The palette is fixed up front and every frame must be the size
+/// of the first.
+pub struct GifWriter<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u16,
+    height: u16,
+    indices: HashMap<Rgb, u8>,
+    delay: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Starts a GIF of `width` by `height` pixels using at most 256 colours
+    /// from `palette`, showing each frame for `delay` hundredths of a second.
+    pub fn new(
+        out: W,
+        width: usize,
+        height: usize,
+        palette: &[Rgb],
+        delay: u16,
+    ) -> io::Result<Self> {
+        let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(invalid(format!(
+                "{}x{} is too large for a GIF",
+                width, height
+            )));
+        };
+        if palette.len() > 256 {
+            return Err(invalid("more than 256 colours".to_string()));
+        }
+
+        let mut encoder =
+            gif::Encoder::new(out, w, h, palette.as_flattened()).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        Ok(GifWriter {
+            encoder,
+            width: w,
+            height: h,
+            indices: (0..).zip(palette).map(|(i, &rgb)| (rgb, i)).collect(),
+            delay,
+        })
+    }
+
+    pub fn write(&mut self, image: &Image) -> io::Result<()> {
+        if (image.width, image.height) != (self.width as usize, self.height as usize) {
+            return Err(invalid("frames differ in size".to_string()));
+        }
+
+        let pixels = image
+            .pixels
+            .iter()
+            .map(|rgb| {
+                self.indices
+                    .get(rgb)
+                    .copied()
+                    .ok_or_else(|| invalid(format!("{:?} is not in the palette", rgb)))
+            })
+            .collect::<io::Result<Vec<u8>>>()?;
+
+        let mut frame = gif::Frame::from_indexed_pixels(self.width, self.height, pixels, None);
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame).map_err(io::Error::other)
+    }
+
+    /// Ends the GIF and returns the writer it was written to.
+    pub fn into_inner(self) -> io::Result<W> {
+        self.encoder.into_inner().map_err(io::Error::other)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Reads every frame of a GIF, such as one written by a [`GifWriter`].
+pub fn read_gif(input: impl Read) -> io::Result<Vec<Image>> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(input).map_err(io::Error::other)?;
+
+    let mut frames = vec![];
+    while let Some(frame) = decoder.read_next_frame().map_err(io::Error::other)? {
+        frames.push(Image {
+            width: frame.width as usize,
+            height: frame.height as usize,
+            pixels: frame
+                .buffer
+                .chunks_exact(4)
+                .map(|rgba| [rgba[0], rgba[1], rgba[2]])
+                .collect(),
+        });
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod raster_tests {
+    use super::*;
+
+    const WHITE: Rgb = [255, 255, 255];
+    const BLACK: Rgb = [0, 0, 0];
+
+    fn checkerboard() -> Image {
+        let grid = Grid::parse("#.\n.#").unwrap();
+
+        Image::from_grid(&grid, 2, |&ch| if ch == '#' { BLACK } else { WHITE })
+    }
+
+    #[test]
+    fn test_from_grid_scales_cells() {
+        let image = checkerboard();
+
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.pixel(1, 1), BLACK);
+        assert_eq!(image.pixel(2, 1), WHITE);
+        assert_eq!(image.pixel(3, 3), BLACK);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut out = vec![];
+        checkerboard().write_ppm(&mut out).unwrap();
+
+        assert!(out.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(out.len(), 11 + 4 * 4 * 3);
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let mut out = vec![];
+        checkerboard().write_png(&mut out).unwrap();
+
+        assert_eq!(
+            Image::read_png(std::io::Cursor::new(out)).unwrap(),
+            checkerboard()
+        );
+    }
+
+    #[test]
+    fn test_gif_writer() {
+        let mut writer = GifWriter::new(vec![], 4, 4, &[BLACK, WHITE], 10).unwrap();
+        writer.write(&checkerboard()).unwrap();
+        writer.write(&checkerboard()).unwrap();
+        let out = writer.into_inner().unwrap();
+        assert!(out.starts_with(b"GIF89a"));
+        assert_eq!(
+            read_gif(out.as_slice()).unwrap(),
+            [checkerboard(), checkerboard()]
+        );
+
+        let mut writer = GifWriter::new(vec![], 4, 4, &[BLACK], 10).unwrap();
+        assert!(writer.write(&checkerboard()).is_err());
+        let mut writer = GifWriter::new(vec![], 2, 2, &[BLACK, WHITE], 10).unwrap();
+        assert!(writer.write(&checkerboard()).is_err());
+        assert!(GifWriter::new(vec![], 1, 1, &[BLACK; 257], 10).is_err());
+    }
+}
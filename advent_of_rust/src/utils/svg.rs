@@ -0,0 +1,87 @@
+use super::grid::Grid;
+use std::fmt::Write;
+
+/// Draws a character grid as an SVG document, one `cell_size` square per
+/// cell. Cells get the background colour `fill` picks for them, if any, and
+/// every character other than `.` is written on top.
+pub fn from_grid<'a>(
+    grid: &Grid<char>,
+    cell_size: usize,
+    fill: impl Fn((usize, usize)) -> Option<&'a str>,
+) -> String {
+    let (width, height) = (grid.width() * cell_size, grid.height() * cell_size);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r##"<rect width="100%" height="100%" fill="#0f0f23"/>"##
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r##"<g font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="#cccccc">"##,
+        cell_size * 4 / 5
+    )
+    .unwrap();
+
+    for (row, col) in grid.positions() {
+        let (x, y) = (col * cell_size, row * cell_size);
+        if let Some(colour) = fill((row, col)) {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x, y, cell_size, cell_size, colour
+            )
+            .unwrap();
+        }
+
+        let ch = grid[(row, col)];
+        if ch != '.' {
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                x + cell_size / 2,
+                y + cell_size / 2,
+                escape(ch)
+            )
+            .unwrap();
+        }
+    }
+
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn escape(ch: char) -> String {
+    match ch {
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '&' => "&amp;".to_string(),
+        _ => ch.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_grid() {
+        let grid = Grid::parse("a.\n.<").unwrap();
+        let svg = from_grid(&grid, 10, |pos| (pos == (1, 1)).then_some("red"));
+
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20""#)
+        );
+        assert!(svg.contains(r#"<text x="5" y="5">a</text>"#));
+        assert!(svg.contains(r#"<rect x="10" y="10" width="10" height="10" fill="red"/>"#));
+        assert!(svg.contains(r#"<text x="15" y="15">&lt;</text>"#));
+        assert_eq!(svg.matches("<text").count(), 2);
+    }
+}
@@ -0,0 +1,346 @@
+use super::grid::{Grid, GridError};
+use clap::ValueEnum;
+use std::fmt;
+use std::iter::successors;
+
+/// The eight directions a word can be read in, using screen coordinates
+/// (north is up, i.e. decreasing row).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Direction {
+    N,
+    Ne,
+    E,
+    Se,
+    S,
+    Sw,
+    W,
+    Nw,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::N,
+        Direction::Ne,
+        Direction::E,
+        Direction::Se,
+        Direction::S,
+        Direction::Sw,
+        Direction::W,
+        Direction::Nw,
+    ];
+
+    /// `(d_row, d_col)` of a single step in this direction.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::N => (-1, 0),
+            Direction::Ne => (-1, 1),
+            Direction::E => (0, 1),
+            Direction::Se => (1, 1),
+            Direction::S => (1, 0),
+            Direction::Sw => (1, -1),
+            Direction::W => (0, -1),
+            Direction::Nw => (-1, -1),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::N => "N",
+            Direction::Ne => "NE",
+            Direction::E => "E",
+            Direction::Se => "SE",
+            Direction::S => "S",
+            Direction::Sw => "SW",
+            Direction::W => "W",
+            Direction::Nw => "NW",
+        };
+
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// `AA` occurs twice in `AAA`
+    Allowed,
+    /// `AA` occurs once in `AAA`; matches along the same line never share cells
+    Disallowed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordMatch {
+    pub start: (usize, usize),
+    pub direction: Direction,
+    pub len: usize,
+}
+
+impl WordMatch {
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (row, col) = self.start;
+        let (d_row, d_col) = self.direction.delta();
+
+        (0..self.len as isize).map(move |i| {
+            (
+                row.wrapping_add_signed(i * d_row),
+                col.wrapping_add_signed(i * d_col),
+            )
+        })
+    }
+}
+
+/// Finds every occurrence of `word` reading in any of `directions`.
+pub fn find_word(
+    grid: &Grid<char>,
+    word: &str,
+    directions: &[Direction],
+    overlap: Overlap,
+) -> Vec<WordMatch> {
+    let word: Vec<char> = word.chars().collect();
+    let mut matches = vec![];
+    if word.is_empty() {
+        return matches;
+    }
+
+    for &direction in directions {
+        let (d_row, d_col) = direction.delta();
+        let line_starts = grid
+            .positions()
+            .filter(|&pos| grid.offset(pos, (-d_row, -d_col)).is_none());
+
+        for line_start in line_starts {
+            let line: Vec<(usize, usize)> =
+                successors(Some(line_start), |&pos| grid.offset(pos, (d_row, d_col))).collect();
+
+            let mut i = 0;
+            while i + word.len() <= line.len() {
+                if word
+                    .iter()
+                    .zip(&line[i..])
+                    .all(|(ch, &pos)| grid[pos] == *ch)
+                {
+                    matches.push(WordMatch {
+                        start: line[i],
+                        direction,
+                        len: word.len(),
+                    });
+
+                    if overlap == Overlap::Disallowed {
+                        i += word.len();
+                        continue;
+                    }
+                }
+
+                i += 1;
+            }
+        }
+    }
+
+    matches
+}
+
+/// Why a pattern isn't a usable [`Stencil`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StencilError {
+    Grid(GridError),
+    /// The row with this index has no cells, e.g. from `M.S//M.S` or `M.S/`
+    EmptyRow(usize),
+    /// Every cell is a wildcard, so the stencil would match everywhere
+    NoFixedCell,
+}
+
+impl fmt::Display for StencilError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StencilError::Grid(err) => err.fmt(f),
+            StencilError::EmptyRow(row) => write!(f, "row {} is empty", row),
+            StencilError::NoFixedCell => write!(
+                f,
+                "every cell is a wildcard '{}', so it would match anywhere",
+                Stencil::WILDCARD
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StencilError {}
+
+impl From<GridError> for StencilError {
+    fn from(err: GridError) -> Self {
+        StencilError::Grid(err)
+    }
+}
+
+/// A 2D pattern such as the X-MAS cross, where [`Stencil::WILDCARD`] cells
+/// match anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil {
+    cells: Grid<Option<char>>,
+}
+
+impl Stencil {
+    pub const WILDCARD: char = '.';
+
+    /// Parses one row per line, or per `/`-separated segment so patterns can
+    /// be passed on the command line, e.g. `M.S/.A./M.S`. Every row needs a
+    /// cell and at least one cell must not be a wildcard.
+    pub fn parse(pattern: &str) -> Result<Self, StencilError> {
+        let rows = pattern.lines().flat_map(|line| line.split('/'));
+        if let Some(row) = rows.clone().position(|row| row.trim_end().is_empty()) {
+            return Err(StencilError::EmptyRow(row));
+        }
+
+        let grid = Grid::parse(&rows.collect::<Vec<_>>().join("\n"))?;
+        if grid.positions().all(|pos| grid[pos] == Self::WILDCARD) {
+            return Err(StencilError::NoFixedCell);
+        }
+
+        Ok(Stencil {
+            cells: grid.map(|&ch| (ch != Self::WILDCARD).then_some(ch)),
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.height()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.width()
+    }
+
+    pub fn rotate_clockwise(&self) -> Stencil {
+        let (height, width) = (self.height(), self.width());
+
+        Stencil {
+            cells: Grid::from_fn(width, height, |(row, col)| {
+                self.cells[(height - 1 - col, row)]
+            }),
+        }
+    }
+
+    /// The distinct stencils among this one and its 90, 180 and 270 degree
+    /// rotations.
+    pub fn rotations(&self) -> Vec<Stencil> {
+        let mut rotations: Vec<Stencil> = vec![];
+        let mut current = self.clone();
+
+        for _ in 0..4 {
+            let next = current.rotate_clockwise();
+            if !rotations.contains(&current) {
+                rotations.push(current);
+            }
+            current = next;
+        }
+
+        rotations
+    }
+
+    /// Top-left corners of every placement of the stencil that fits in the
+    /// grid and agrees with it on every non-wildcard cell.
+    pub fn find(&self, grid: &Grid<char>) -> Vec<(usize, usize)> {
+        if self.height() > grid.height() || self.width() > grid.width() {
+            return vec![];
+        }
+
+        let rows = 0..=grid.height() - self.height();
+        rows.flat_map(|row| (0..=grid.width() - self.width()).map(move |col| (row, col)))
+            .filter(|&origin| self.cells_at(origin).all(|(pos, ch)| grid[pos] == ch))
+            .collect()
+    }
+
+    /// Grid positions and expected characters of the non-wildcard cells when
+    /// the stencil is placed at `origin`.
+    pub fn cells_at(
+        &self,
+        (row, col): (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), char)> + '_ {
+        self.cells
+            .positions()
+            .filter_map(move |pos| Some(((row + pos.0, col + pos.1), self.cells[pos]?)))
+    }
+
+    /// The cell in the middle of the stencil when placed at `origin`.
+    pub fn center(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        (row + self.height() / 2, col + self.width() / 2)
+    }
+}
+
+#[cfg(test)]
+mod word_search_tests {
+    use super::*;
+
+    #[test]
+    fn test_find_word_directions() {
+        let grid = Grid::parse("XMAS\nMM..\nA.A.\nS..S").unwrap();
+
+        let matches = find_word(&grid, "XMAS", &Direction::ALL, Overlap::Allowed);
+        let directions: Vec<_> = matches.iter().map(|m| m.direction).collect();
+        assert_eq!(directions, vec![Direction::E, Direction::Se, Direction::S]);
+        assert_eq!(
+            matches[1].cells().collect::<Vec<_>>(),
+            vec![(0, 0), (1, 1), (2, 2), (3, 3)]
+        );
+
+        let matches = find_word(
+            &grid,
+            "SAMX",
+            &[Direction::N, Direction::W],
+            Overlap::Allowed,
+        );
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[test]
+    fn test_find_word_overlap() {
+        let grid = Grid::parse("AAAAA").unwrap();
+
+        assert_eq!(
+            find_word(&grid, "AA", &[Direction::E], Overlap::Allowed).len(),
+            4
+        );
+        assert_eq!(
+            find_word(&grid, "AA", &[Direction::E], Overlap::Disallowed).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_stencil_rotations() {
+        let x_mas = Stencil::parse("M.S/.A./M.S").unwrap();
+        let rotations = x_mas.rotations();
+
+        assert_eq!(rotations.len(), 4);
+        assert_eq!(rotations[1], Stencil::parse("M.M/.A./S.S").unwrap());
+        assert_eq!(Stencil::parse("A.A/.A./A.A").unwrap().rotations().len(), 1);
+    }
+
+    #[test]
+    fn test_stencil_find() {
+        let grid = Grid::parse("MMS.\n.AM.\nMSS.\n...A").unwrap();
+        let x_mas = Stencil::parse("M.S/.A./M.S").unwrap();
+
+        assert_eq!(x_mas.find(&grid), vec![(0, 0)]);
+        assert_eq!(x_mas.center((0, 0)), (1, 1));
+    }
+
+    #[test]
+    fn test_stencil_rejects_empty_rows() {
+        assert_eq!(Stencil::parse("M.S//M.S"), Err(StencilError::EmptyRow(1)));
+        assert_eq!(Stencil::parse("M.S/.A./"), Err(StencilError::EmptyRow(2)));
+        assert_eq!(
+            Stencil::parse(""),
+            Err(StencilError::Grid(GridError::Empty))
+        );
+        assert_eq!(
+            Stencil::parse("M.S\n.A.\nM.S\n"),
+            Stencil::parse("M.S/.A./M.S")
+        );
+    }
+
+    #[test]
+    fn test_stencil_rejects_only_wildcards() {
+        assert_eq!(Stencil::parse("../.."), Err(StencilError::NoFixedCell));
+        assert!(Stencil::parse("../.X").is_ok());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::days::{Days, Part};
+use crate::runner::{self, Limits, Verdict};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Files in a day's directory that trigger a re-run.
+const WATCHED: [&str; 3] = ["mod.rs", "example.txt", "input.txt"];
+
+/// How long to keep collecting events after the first one, as editors often
+/// save a file in several steps.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// One line comparing a part's outcome with the previous run's.
+pub fn diff(part: Part, previous: Option<&Verdict>, current: &Verdict) -> String {
+    let name = match part {
+        Part::One => "part one",
+        Part::Two => "part two",
+    };
+
+    match previous {
+        None => format!("{}: {}", name, current),
+        Some(previous) if previous == current => format!("{}: {} (unchanged)", name, current),
+        Some(previous) => format!("{}: {} -> {}", name, previous, current),
+    }
+}
+
+/// Whether `event` changed one of the day's watched files.
+pub fn is_relevant(event: &Event) -> bool {
+    let changed = matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    );
+
+    changed
+        && event.paths.iter().any(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| WATCHED.contains(&name))
+        })
+}
+
+/// Rebuilds and re-runs `day` every time its module, example or input
+/// changes: first its tests, which check the example answers, then both parts
+/// on the real input, each in its own process within `limits`. Runs until
+/// interrupted.
+pub fn watch(day: Days, limits: &Limits) -> notify::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(Path::new(day.dir()), RecursiveMode::NonRecursive)?;
+
+    let mut previous = HashMap::new();
+    loop {
+        run_once(day, limits, &mut previous);
+        println!("Watching {} for changes...", day.dir());
+
+        loop {
+            let Ok(event) = rx.recv() else {
+                return Ok(());
+            };
+            if is_relevant(&event?) {
+                break;
+            }
+        }
+        while rx.recv_timeout(SETTLE).is_ok() {}
+    }
+}
+
+fn run_once(day: Days, limits: &Limits, previous: &mut HashMap<Part, Verdict>) {
+    let number = day.day_number();
+
+    println!("\n[day {}] building", number);
+    let Some(exe) = build() else {
+        return println!("[day {}] build failed", number);
+    };
+
+    println!("[day {}] testing the example", number);
+    let filter = format!("days::day{:02}::", number);
+    if !cargo(&["test", "--quiet", "--lib", &filter])
+        .status()
+        .is_ok_and(|status| status.success())
+    {
+        return println!("[day {}] tests failed, skipping the input", number);
+    }
+
+    println!("[day {}] running the input", number);
+    for part in [Part::One, Part::Two] {
+        let verdict = match runner::run_isolated(runner::solver(&exe, day, part, None), limits) {
+            Ok((verdict, _)) => verdict,
+            Err(err) => Verdict::Failed(err.to_string()),
+        };
+
+        println!("{}", diff(part, previous.get(&part), &verdict));
+        previous.insert(part, verdict);
+    }
+}
+
+/// Builds the binary, returning where cargo put it. The binary is run
+/// directly rather than through `cargo run`, so that killing it on a timeout
+/// doesn't leave it running under a killed cargo.
+fn build() -> Option<PathBuf> {
+    let output = cargo(&[
+        "build",
+        "--quiet",
+        "--bin",
+        env!("CARGO_PKG_NAME"),
+        "--message-format=json-render-diagnostics",
+    ])
+    .stderr(Stdio::inherit())
+    .output()
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+        .find_map(|message| message["executable"].as_str().map(PathBuf::from))
+}
+
+/// Cargo in this crate, so the watcher works from any directory.
+fn cargo(args: &[&str]) -> Command {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command.args(args).current_dir(env!("CARGO_MANIFEST_DIR"));
+
+    command
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, DataChange, ModifyKind};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_diff() {
+        let answer = |a: &str| Verdict::Answer(a.to_string());
+
+        assert_eq!(diff(Part::One, None, &answer("41")), "part one: 41");
+        assert_eq!(
+            diff(Part::Two, Some(&answer("6")), &answer("6")),
+            "part two: 6 (unchanged)"
+        );
+        assert_eq!(
+            diff(
+                Part::One,
+                Some(&answer("41")),
+                &Verdict::Panicked("index out of bounds".to_string())
+            ),
+            "part one: 41 -> panicked: index out of bounds"
+        );
+    }
+
+    #[test]
+    fn test_is_relevant() {
+        let event = |kind, name: &str| Event::new(kind).add_path(PathBuf::from("day06").join(name));
+        let modify = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+
+        assert!(is_relevant(&event(modify, "mod.rs")));
+        assert!(is_relevant(&event(
+            EventKind::Create(CreateKind::File),
+            "example.txt"
+        )));
+        assert!(!is_relevant(&event(modify, ".mod.rs.swp")));
+        assert!(!is_relevant(&event(
+            EventKind::Access(AccessKind::Any),
+            "input.txt"
+        )));
+    }
+}
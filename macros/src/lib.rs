@@ -1,10 +1,89 @@
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
 use std::fs;
 use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parse_macro_input, Ident, Token};
+
+/// The days implementing each optional trait, written
+/// `explain: [day02, ...], visualize: [day04, ...]`. Listing them explicitly
+/// lets the compiler check every dispatch.
+#[derive(Default)]
+struct Registrations {
+    explain: Vec<Ident>,
+    visualize: Vec<Ident>,
+}
+
+impl Registrations {
+    /// Rejects any registered name that isn't one of the day `modules`,
+    /// pointing at the name in the macro call.
+    fn check(&self, modules: &[String]) -> syn::Result<()> {
+        for day in self.explain.iter().chain(&self.visualize) {
+            if !modules.contains(&day.to_string()) {
+                return Err(syn::Error::new(
+                    day.span(),
+                    format!("no day module named `{}`", day),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn explains(&self, module: &str) -> bool {
+        self.explain.iter().any(|day| day == module)
+    }
+
+    fn visualizes(&self, module: &str) -> bool {
+        self.visualize.iter().any(|day| day == module)
+    }
+}
+
+/// The day number, `Days` variant and module of a `dayNN` module name.
+fn day_idents(module: &str) -> (usize, Ident, Ident) {
+    let day_number = module[3..].parse::<usize>().unwrap();
+    let variant_ident = Ident::new(&format!("Day{:02}", day_number), Span::call_site());
+    let module_ident = Ident::new(module, Span::call_site());
+    (day_number, variant_ident, module_ident)
+}
+
+impl Parse for Registrations {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut registrations = Registrations::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+            let content;
+            bracketed!(content in input);
+            let days = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect();
+
+            match name.to_string().as_str() {
+                "explain" => registrations.explain = days,
+                "visualize" => registrations.visualize = days,
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "expected `explain` or `visualize`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(registrations)
+    }
+}
 
 #[proc_macro]
-pub fn generate_days_enum(_input: TokenStream) -> TokenStream {
+pub fn generate_days_enum(input: TokenStream) -> TokenStream {
+    let registrations = parse_macro_input!(input as Registrations);
     let manifest_dir = PathBuf::from(
         std::env::var("CARGO_MANIFEST_DIR")
             .expect("CARGO_MANIFEST_DIR environment variable is not set"),
@@ -25,66 +104,101 @@ pub fn generate_days_enum(_input: TokenStream) -> TokenStream {
         }
     }
 
+    modules.sort();
+
+    if let Err(err) = registrations.check(&modules) {
+        return err.to_compile_error().into();
+    }
+
     let enum_variants = modules.iter().map(|module| {
-        let day_number = module[3..].parse::<usize>().unwrap();
-        let variant_ident = syn::Ident::new(
-            &format!("Day{:02}", day_number),
-            proc_macro2::Span::call_site(),
-        );
+        let (_, variant_ident, _) = day_idents(module);
         quote! { #variant_ident }
     });
 
     let from_day_arms = modules.iter().map(|module| {
-        let day_number = module[3..].parse::<usize>().unwrap();
-        let variant_ident = syn::Ident::new(
-            &format!("Day{:02}", day_number),
-            proc_macro2::Span::call_site(),
-        );
+        let (day_number, variant_ident, _) = day_idents(module);
         quote! { #day_number => Some(Self::#variant_ident), }
     });
 
-    let part_one_arms = modules.iter().map(|module| {
-        let day_number = module[3..].parse::<usize>().unwrap();
-        let variant_ident = syn::Ident::new(
-            &format!("Day{:02}", day_number),
-            proc_macro2::Span::call_site(),
-        );
-        let module_ident = syn::Ident::new(module, proc_macro2::Span::call_site());
+    let input_arms = modules.iter().map(|module| {
+        let (day_number, variant_ident, _) = day_idents(module);
         let binding = manifest_dir.join(format!("src/days/day{:02}/input.txt", day_number));
         let input_path = binding.to_string_lossy();
+        quote! { Self::#variant_ident => #input_path, }
+    });
+
+    let all_days = modules.iter().map(|module| {
+        let (_, variant_ident, _) = day_idents(module);
+        quote! { Self::#variant_ident, }
+    });
+
+    let day_number_arms = modules.iter().map(|module| {
+        let (day_number, variant_ident, _) = day_idents(module);
+        quote! { Self::#variant_ident => #day_number, }
+    });
+
+    let dir_arms = modules.iter().map(|module| {
+        let (day_number, variant_ident, _) = day_idents(module);
+        let binding = manifest_dir.join(format!("src/days/day{:02}", day_number));
+        let dir = binding.to_string_lossy();
+        quote! { Self::#variant_ident => #dir, }
+    });
+
+    let solve_arms = modules.iter().map(|module| {
+        let (_, variant_ident, module_ident) = day_idents(module);
         quote! {
-            Self::#variant_ident => {
-                let input = std::fs::read_to_string(#input_path)
-                    .expect("Failed to load input file");
-                Box::new(#module_ident::DayStruct.part_one(&input))
+            Self::#variant_ident => match part {
+                Part::One => Box::new(#module_ident::DayStruct.part_one(input)),
+                Part::Two => Box::new(#module_ident::DayStruct.part_two(input)),
+            },
+        }
+    });
+
+    let explain_arms = modules.iter().map(|module| {
+        let (_, variant_ident, module_ident) = day_idents(module);
+        if registrations.explains(module) {
+            quote! {
+                Self::#variant_ident => Some(explain(&#module_ident::DayStruct, input, part, format)),
             }
+        } else {
+            quote! { Self::#variant_ident => None, }
         }
     });
 
-    let part_two_arms = modules.iter().map(|module| {
-        let day_number = module[3..].parse::<usize>().unwrap();
-        let variant_ident = syn::Ident::new(
-            &format!("Day{:02}", day_number),
-            proc_macro2::Span::call_site(),
-        );
-        let module_ident = syn::Ident::new(module, proc_macro2::Span::call_site());
-        let binding = manifest_dir.join(format!("src/days/day{:02}/input.txt", day_number));
-        let input_path = binding.to_string_lossy();
-        quote! {
-            Self::#variant_ident => {
-                let input = std::fs::read_to_string(#input_path)
-                    .expect("Failed to load input file");
-                Box::new(#module_ident::DayStruct.part_two(&input))
+    let visualize_arms = modules.iter().map(|module| {
+        let (_, variant_ident, module_ident) = day_idents(module);
+        if registrations.visualizes(module) {
+            quote! {
+                Self::#variant_ident => {
+                    #module_ident::DayStruct.visualize(input, part, sink);
+                    sink.finish();
+                    true
+                }
             }
+        } else {
+            quote! { Self::#variant_ident => false, }
+        }
+    });
+
+    let colours_arms = modules.iter().map(|module| {
+        let (_, variant_ident, module_ident) = day_idents(module);
+        if registrations.visualizes(module) {
+            quote! { Self::#variant_ident => #module_ident::DayStruct.colours(), }
+        } else {
+            quote! { Self::#variant_ident => ColourMap::default(), }
         }
     });
 
     let expanded = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub enum Days {
             #(#enum_variants),*
         }
 
         impl Days {
+            /// Every implemented day, in order.
+            pub const ALL: &'static [Days] = &[#(#all_days)*];
+
             pub fn from_day_number(day: usize) -> Option<Self> {
                 match day {
                     #(#from_day_arms)*
@@ -92,15 +206,64 @@ pub fn generate_days_enum(_input: TokenStream) -> TokenStream {
                 }
             }
 
-            pub fn part_one(&self) -> Box<dyn std::fmt::Display> {
+            pub fn day_number(&self) -> usize {
                 match self {
-                    #(#part_one_arms)*
+                    #(#day_number_arms)*
                 }
             }
 
+            /// The day's module directory, holding its input and example.
+            pub fn dir(&self) -> &'static str {
+                match self {
+                    #(#dir_arms)*
+                }
+            }
+
+            pub fn input_path(&self) -> &'static str {
+                match self {
+                    #(#input_arms)*
+                }
+            }
+
+            pub fn input(&self) -> String {
+                std::fs::read_to_string(self.input_path()).expect("Failed to load input file")
+            }
+
+            pub fn part_one(&self) -> Box<dyn std::fmt::Display> {
+                self.solve(Part::One, &self.input())
+            }
+
             pub fn part_two(&self) -> Box<dyn std::fmt::Display> {
+                self.solve(Part::Two, &self.input())
+            }
+
+            /// Solves `part` for `input` rather than the day's own input.
+            pub fn solve(&self, part: Part, input: &str) -> Box<dyn std::fmt::Display> {
+                match self {
+                    #(#solve_arms)*
+                }
+            }
+
+            /// Sends the day's frames for `input` to `sink`, or returns `false`
+            /// if the day doesn't implement `Visualize`.
+            pub fn visualize(&self, input: &str, part: Part, sink: &mut dyn FrameSink) -> bool {
+                match self {
+                    #(#visualize_arms)*
+                }
+            }
+
+            /// The day's colours for exporting its frames as images.
+            pub fn colours(&self) -> ColourMap {
+                match self {
+                    #(#colours_arms)*
+                }
+            }
+
+            /// Explains `part` for `input`, or `None` if the day doesn't
+            /// implement `Explain`.
+            pub fn explain(&self, input: &str, part: Part, format: ExplainFormat) -> Option<String> {
                 match self {
-                    #(#part_two_arms)*
+                    #(#explain_arms)*
                 }
             }
         }